@@ -6,6 +6,7 @@ fn main() {
     }
 }
 
+#[allow(dead_code)]
 enum PuzzleType {
     Easy5x5,
     Easy7x7,
@@ -95,10 +96,10 @@ fn get_puzzle_data(document: &Html) -> String {
             let cell_text = cell.text().collect::<String>();
             match cell_text.parse::<u32>() {
                 Ok(number) => puzzle_data.push_str(&number.to_string()),
-                Err(_) => puzzle_data.push_str("."),
+                Err(_) => puzzle_data.push('.'),
             }
         }
-        puzzle_data.push_str("\n");
+        puzzle_data.push('\n');
     }
 
     puzzle_data
@@ -0,0 +1,77 @@
+use std::str::FromStr;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use nurikabe::strategy::{
+    avoid_pools::AvoidPools, complete_islands::CompleteIslands, confinement::Confinement,
+    dual_liberties::DualLiberties, hypotheticals::Hypotheticals, single_liberties::SingleLiberties,
+    unreachable_cells::UnreachableCells, Strategy,
+};
+use nurikabe::{Grid, Solver};
+
+/// A black comb: full black rows alternating with rows of black teeth and unknown gaps. Building it
+/// fuses every cell into one region with a very large number of unknown neighbors.
+fn comb(size: usize) -> String {
+    (0..size)
+        .map(|row| {
+            (0..size)
+                .map(|col| {
+                    if row % 2 == 0 || col % 2 == 0 {
+                        'B'
+                    } else {
+                        '.'
+                    }
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn basic_strategies() -> Vec<Box<dyn Strategy>> {
+    vec![
+        Box::new(CompleteIslands),
+        Box::new(SingleLiberties),
+        Box::new(DualLiberties),
+        Box::new(AvoidPools),
+        Box::new(UnreachableCells),
+    ]
+}
+
+fn bench_mark_cells(c: &mut Criterion) {
+    let mut group = c.benchmark_group("mark_cells");
+    for size in [16, 32, 64] {
+        let input = comb(size);
+        group.bench_with_input(BenchmarkId::new("comb", size), &input, |b, input| {
+            b.iter(|| Grid::from_str(input).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn bench_solve(c: &mut Criterion) {
+    // https://en.wikipedia.org/wiki/Nurikabe_(puzzle)
+    let grid = Grid::from_str(concat!(
+        "2........2\n",
+        "......2...\n",
+        ".2..7.....\n",
+        "..........\n",
+        "......3.3.\n",
+        "..2....3..\n",
+        "2..4......\n",
+        "..........\n",
+        ".1....2.4.",
+    ))
+    .unwrap();
+
+    let mut strategies = basic_strategies();
+    strategies.push(Box::new(Confinement));
+    strategies.push(Box::new(Hypotheticals::new(basic_strategies())));
+    let solver = Solver::new(strategies);
+
+    c.bench_function("solve_wikipedia", |b| {
+        b.iter(|| solver.solve(&mut grid.clone()).unwrap())
+    });
+}
+
+criterion_group!(benches, bench_mark_cells, bench_solve);
+criterion_main!(benches);
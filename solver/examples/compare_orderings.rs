@@ -0,0 +1,95 @@
+//! Solves every puzzle in a directory once per candidate ordering, and reports how long each
+//! ordering took.
+//!
+//! Usage: `cargo run --release --example compare_orderings [PUZZLE_DIR]`
+
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use std::{env, fs};
+
+use nurikabe::strategy::{
+    avoid_pools::AvoidPools,
+    complete_islands::CompleteIslands,
+    confinement::Confinement,
+    dual_liberties::DualLiberties,
+    hypotheticals::{Hypotheticals, HypotheticalsOptions},
+    ordering::builtin_orderings,
+    single_liberties::SingleLiberties,
+    unreachable_cells::UnreachableCells,
+    Strategy,
+};
+use nurikabe::{Grid, Solver};
+
+fn basic_strategies() -> Vec<Box<dyn Strategy>> {
+    vec![
+        Box::new(CompleteIslands),
+        Box::new(SingleLiberties),
+        Box::new(DualLiberties),
+        Box::new(AvoidPools),
+        Box::new(UnreachableCells),
+    ]
+}
+
+fn collect_puzzles(dir: &Path, puzzles: &mut Vec<PathBuf>) {
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            collect_puzzles(&path, puzzles);
+        } else if path.extension().is_some_and(|extension| extension == "txt") {
+            puzzles.push(path);
+        }
+    }
+}
+
+fn main() {
+    let dir = env::args()
+        .nth(1)
+        .unwrap_or_else(|| "data/puzzles".to_string());
+
+    let mut puzzles = vec![];
+    collect_puzzles(Path::new(&dir), &mut puzzles);
+    puzzles.sort();
+
+    let grids = puzzles
+        .iter()
+        .map(|path| Grid::from_str(&fs::read_to_string(path).unwrap()).unwrap())
+        .collect::<Vec<_>>();
+
+    println!("{} puzzles in {}", grids.len(), dir);
+    println!(
+        "{:<24}{:>8}{:>8}{:>14}",
+        "ordering", "solved", "failed", "total time"
+    );
+
+    for ordering in builtin_orderings() {
+        let mut strategies = basic_strategies();
+        strategies.push(Box::new(Confinement));
+        strategies.push(Box::new(Hypotheticals::with_options(
+            basic_strategies(),
+            HypotheticalsOptions {
+                ordering: ordering.clone(),
+                ..Default::default()
+            },
+        )));
+        let solver = Solver::new(strategies);
+
+        let mut num_solved = 0;
+        let mut total_time = Duration::ZERO;
+        for grid in &grids {
+            let start = Instant::now();
+            if solver.solve(&mut grid.clone()).is_ok() {
+                num_solved += 1;
+            }
+            total_time += start.elapsed();
+        }
+
+        println!(
+            "{:<24}{:>8}{:>8}{:>14.3?}",
+            ordering.name(),
+            num_solved,
+            grids.len() - num_solved,
+            total_time
+        );
+    }
+}
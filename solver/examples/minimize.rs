@@ -0,0 +1,46 @@
+//! Reduces the clues of a puzzle while it stays uniquely solvable with a solver profile, and prints
+//! the puzzle with the fewest clues found.
+//!
+//! Usage: `cargo run --release --example minimize PUZZLE_FILE [PROFILE] [--allow-unknown-sizes]`
+
+use std::str::FromStr;
+use std::{env, fs};
+
+use nurikabe::config::SolverConfig;
+use nurikabe::minimizer::{Minimizer, MinimizerConfig};
+use nurikabe::Grid;
+
+fn main() {
+    let mut args = vec![];
+    let mut allow_unknown_sizes = false;
+    for arg in env::args().skip(1) {
+        if arg == "--allow-unknown-sizes" {
+            allow_unknown_sizes = true;
+        } else {
+            args.push(arg);
+        }
+    }
+    let path = args.first().expect("missing puzzle file");
+    let mut grid = Grid::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+    let profile = args
+        .get(1)
+        .cloned()
+        .unwrap_or_else(|| SolverConfig::default_profile(grid.rules()).to_string());
+    let solver_config = SolverConfig::profile(&profile).unwrap();
+
+    // The puzzle files only contain the clues, so solve them first
+    solver_config.build().unwrap().solve(&mut grid).unwrap();
+    let num_clues = grid.clues().len();
+
+    let mut config = MinimizerConfig::new(solver_config);
+    config.allow_unknown_sizes = allow_unknown_sizes;
+    let puzzle = Minimizer::new(config).unwrap().minimize(&grid).unwrap();
+
+    println!(
+        "{} -> {} clues, using {}",
+        num_clues,
+        puzzle.clues.clues().len(),
+        puzzle.strategies_used.join(", ")
+    );
+    println!("{}", puzzle.clues.to_input_string());
+}
@@ -0,0 +1,513 @@
+//! Declarative solver configurations, so the strategies a [`Solver`] uses can be tuned without
+//! recompiling.
+//!
+//! A configuration is either one of the built-in [profiles](SolverConfig::PROFILES), or (with the
+//! `config` feature) a TOML file like the following:
+//!
+//! ```toml
+//! # Start from a built-in profile; any of the keys below override it
+//! profile = "human-advanced"
+//!
+//! strategies = ["CompleteIslands", "SingleLiberties", "DualLiberties", "AvoidPools", "Hypotheticals"]
+//!
+//! [hypotheticals]
+//! depth = 1
+//! # Strategies used to propagate each hypothesis; defaults to the ones above
+//! strategies = ["CompleteIslands", "SingleLiberties", "DualLiberties", "AvoidPools"]
+//! ordering = "MostConstrained"
+//! on_solution = "adopt-if-unique"
+//!
+//! [budget]
+//! time_limit_secs = 10.0
+//! max_hypotheses = 100000
+//! ```
+
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+use std::time::Duration;
+
+#[cfg(feature = "config")]
+use serde::Deserialize;
+
+use crate::strategy::hypotheticals::{Hypotheticals, HypotheticalsOptions, SolutionPolicy};
+use crate::strategy::ordering::builtin_orderings;
+use crate::strategy::registry::StrategyRegistry;
+use crate::{Budget, RuleSet, Solver};
+
+/// Describes how to build a [`Solver`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct SolverConfig {
+    /// Names of the strategies to apply, in order, as registered in a [`StrategyRegistry`].
+    /// `"Hypotheticals"` refers to the strategy described by [`SolverConfig::hypotheticals`].
+    pub strategies: Vec<String>,
+    pub hypotheticals: HypotheticalsConfig,
+    pub budget: BudgetConfig,
+}
+
+/// Settings for the `Hypotheticals` strategy, see [`HypotheticalsOptions`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct HypotheticalsConfig {
+    /// Nesting level, see [`Hypotheticals::nested`].
+    pub depth: usize,
+    /// Names of the strategies used to propagate each hypothesis, or `None` to use the other
+    /// strategies of the solver.
+    pub strategies: Option<Vec<String>>,
+    /// Name of the [`CandidateOrdering`](crate::strategy::ordering::CandidateOrdering) to use.
+    pub ordering: String,
+    /// Maximum number of cells (not hypotheses) to try, see [`HypotheticalsOptions::max_trials`].
+    pub max_trials: Option<usize>,
+    pub learn_from_both_branches: bool,
+    pub on_solution: SolutionPolicy,
+    pub transposition_table_capacity: Option<usize>,
+    /// See [`HypotheticalsOptions::parallel`]: without the `parallel` feature, candidates are
+    /// evaluated one after another, with the same results.
+    pub parallel: bool,
+}
+
+impl Default for HypotheticalsConfig {
+    fn default() -> Self {
+        Self {
+            depth: 1,
+            strategies: None,
+            ordering: "RowMajor".to_string(),
+            max_trials: None,
+            learn_from_both_branches: false,
+            on_solution: SolutionPolicy::default(),
+            transposition_table_capacity: None,
+            parallel: false,
+        }
+    }
+}
+
+/// Settings for the [`Budget`] of a solve.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "config", derive(Deserialize))]
+#[cfg_attr(feature = "config", serde(default, deny_unknown_fields))]
+pub struct BudgetConfig {
+    pub time_limit_secs: Option<f64>,
+    pub max_applications: Option<usize>,
+    pub max_hypotheses: Option<usize>,
+}
+
+/// The keys of the `[hypotheticals]` section of a TOML configuration. Each key that is given
+/// overrides the corresponding setting of the profile.
+#[cfg(feature = "config")]
+#[derive(Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct HypotheticalsOverlay {
+    depth: Option<usize>,
+    strategies: Option<Vec<String>>,
+    ordering: Option<String>,
+    max_trials: Option<usize>,
+    learn_from_both_branches: Option<bool>,
+    on_solution: Option<SolutionPolicy>,
+    transposition_table_capacity: Option<usize>,
+    parallel: Option<bool>,
+}
+
+#[cfg(feature = "config")]
+impl HypotheticalsOverlay {
+    fn merge(self, config: HypotheticalsConfig) -> HypotheticalsConfig {
+        HypotheticalsConfig {
+            depth: self.depth.unwrap_or(config.depth),
+            strategies: self.strategies.or(config.strategies),
+            ordering: self.ordering.unwrap_or(config.ordering),
+            max_trials: self.max_trials.or(config.max_trials),
+            learn_from_both_branches: self
+                .learn_from_both_branches
+                .unwrap_or(config.learn_from_both_branches),
+            on_solution: self.on_solution.unwrap_or(config.on_solution),
+            transposition_table_capacity: self
+                .transposition_table_capacity
+                .or(config.transposition_table_capacity),
+            parallel: self.parallel.unwrap_or(config.parallel),
+        }
+    }
+}
+
+impl BudgetConfig {
+    /// Returns the described budget. Fails if the time limit is negative, not a number, or too
+    /// large to represent.
+    pub fn to_budget(&self) -> Result<Budget, ConfigError> {
+        let time_limit = self
+            .time_limit_secs
+            .map(|secs| {
+                Duration::try_from_secs_f64(secs).map_err(|_| ConfigError::InvalidTimeLimit(secs))
+            })
+            .transpose()?;
+        Ok(Budget {
+            time_limit,
+            max_applications: self.max_applications,
+            max_hypotheses: self.max_hypotheses,
+            ..Default::default()
+        })
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    UnknownProfile(String),
+    UnknownStrategy(String),
+    UnknownOrdering(String),
+    /// `Hypotheticals` was configured with a depth of 0.
+    InvalidDepth,
+    /// The time limit of the budget is negative, not a number, or too large.
+    InvalidTimeLimit(f64),
+    #[cfg(feature = "config")]
+    Io(std::io::Error),
+    #[cfg(feature = "config")]
+    Parse(toml::de::Error),
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::UnknownProfile(name) => write!(f, "unknown profile {:?}", name),
+            ConfigError::UnknownStrategy(name) => write!(f, "unknown strategy {:?}", name),
+            ConfigError::UnknownOrdering(name) => write!(f, "unknown ordering {:?}", name),
+            ConfigError::InvalidDepth => write!(f, "hypotheticals depth must be at least 1"),
+            ConfigError::InvalidTimeLimit(secs) => write!(f, "invalid time limit {} s", secs),
+            #[cfg(feature = "config")]
+            ConfigError::Io(err) => write!(f, "failed to read config: {}", err),
+            #[cfg(feature = "config")]
+            ConfigError::Parse(err) => write!(f, "failed to parse config: {}", err),
+        }
+    }
+}
+
+impl Error for ConfigError {}
+
+const BASIC_STRATEGIES: [&str; 4] = [
+    "CompleteIslands",
+    "SingleLiberties",
+    "DualLiberties",
+    "AvoidPools",
+];
+
+impl SolverConfig {
+    /// Names of the built-in profiles:
+    /// - `"human-basic"`: techniques a human solver uses on easy puzzles.
+    /// - `"human-advanced"`: adds the techniques that need to look at the whole grid.
+    /// - `"full"`: adds one level of hypotheticals.
+    /// - `"mochikoro"` and `"nuribou"`: the equivalents of `"full"` for
+    ///   [`Variant::Mochikoro`](crate::Variant::Mochikoro) and
+    ///   [`Variant::Nuribou`](crate::Variant::Nuribou) grids.
+    pub const PROFILES: [&'static str; 5] = [
+        "human-basic",
+        "human-advanced",
+        "full",
+        "mochikoro",
+        "nuribou",
+    ];
+
+    /// Returns the name of the profile to solve grids with the given rules with by default.
+    pub fn default_profile(rules: &dyn RuleSet) -> &'static str {
+        if rules.has_rectangular_islands() {
+            "mochikoro"
+        } else if rules.has_strip_walls() {
+            "nuribou"
+        } else {
+            "full"
+        }
+    }
+
+    /// Returns the built-in profile with the given name.
+    pub fn profile(name: &str) -> Result<Self, ConfigError> {
+        let names = |names: &[&str]| names.iter().map(|name| name.to_string()).collect();
+
+        let strategies = match name {
+            "human-basic" => names(&BASIC_STRATEGIES),
+            "human-advanced" => {
+                names(&[&BASIC_STRATEGIES[..], &["UnreachableCells", "Confinement"]].concat())
+            }
+            "full" => names(
+                &[
+                    &BASIC_STRATEGIES[..],
+                    &["UnreachableCells", "Confinement", "Hypotheticals"],
+                ]
+                .concat(),
+            ),
+            "mochikoro" => names(
+                &[
+                    &BASIC_STRATEGIES[..],
+                    &["Rectangles", "Confinement", "Hypotheticals"],
+                ]
+                .concat(),
+            ),
+            "nuribou" => names(
+                &[
+                    &BASIC_STRATEGIES[..],
+                    &["Strips", "UnreachableCells", "Confinement", "Hypotheticals"],
+                ]
+                .concat(),
+            ),
+            _ => return Err(ConfigError::UnknownProfile(name.to_string())),
+        };
+        // Confinement is too slow to run on every hypothesis
+        let hypothetical_strategies = match name {
+            "mochikoro" => names(&[&BASIC_STRATEGIES[..], &["Rectangles"]].concat()),
+            "nuribou" => names(&[&BASIC_STRATEGIES[..], &["Strips", "UnreachableCells"]].concat()),
+            _ => names(&[&BASIC_STRATEGIES[..], &["UnreachableCells"]].concat()),
+        };
+        let hypotheticals = HypotheticalsConfig {
+            strategies: Some(hypothetical_strategies),
+            ..Default::default()
+        };
+
+        Ok(Self {
+            strategies,
+            hypotheticals,
+            budget: BudgetConfig::default(),
+        })
+    }
+
+    /// Parses a TOML configuration. Keys that are left out, including those within the
+    /// `[hypotheticals]` and `[budget]` sections, are taken from the profile named by the `profile`
+    /// key, or from `"full"` if there is none.
+    #[cfg(feature = "config")]
+    pub fn from_toml(input: &str) -> Result<Self, ConfigError> {
+        #[derive(Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct ConfigFile {
+            profile: Option<String>,
+            strategies: Option<Vec<String>>,
+            #[serde(default)]
+            hypotheticals: HypotheticalsOverlay,
+            #[serde(default)]
+            budget: BudgetConfig,
+        }
+
+        let file: ConfigFile = toml::from_str(input).map_err(ConfigError::Parse)?;
+        let profile = Self::profile(file.profile.as_deref().unwrap_or("full"))?;
+        let config = Self {
+            strategies: file.strategies.unwrap_or(profile.strategies),
+            hypotheticals: file.hypotheticals.merge(profile.hypotheticals),
+            budget: BudgetConfig {
+                time_limit_secs: file
+                    .budget
+                    .time_limit_secs
+                    .or(profile.budget.time_limit_secs),
+                max_applications: file
+                    .budget
+                    .max_applications
+                    .or(profile.budget.max_applications),
+                max_hypotheses: file.budget.max_hypotheses.or(profile.budget.max_hypotheses),
+            },
+        };
+        config.budget.to_budget()?;
+        Ok(config)
+    }
+
+    /// Reads a TOML configuration from a file, see [`SolverConfig::from_toml`].
+    #[cfg(feature = "config")]
+    pub fn from_file(path: impl AsRef<std::path::Path>) -> Result<Self, ConfigError> {
+        let input = std::fs::read_to_string(path).map_err(ConfigError::Io)?;
+        Self::from_toml(&input)
+    }
+
+    /// Builds the solver described by this configuration, using the built-in strategies.
+    pub fn build(&self) -> Result<Solver, ConfigError> {
+        self.build_with_registry(&StrategyRegistry::builtin())
+    }
+
+    /// Builds the solver described by this configuration, looking up strategies in the given
+    /// registry. The built-in `"Hypotheticals"` strategy is built from
+    /// [`SolverConfig::hypotheticals`], while a strategy registered under that name by other means
+    /// is created like any other.
+    pub fn build_with_registry(&self, registry: &StrategyRegistry) -> Result<Solver, ConfigError> {
+        let strategies = self
+            .strategies
+            .iter()
+            .map(|name| {
+                let info = registry
+                    .get(name)
+                    .ok_or_else(|| ConfigError::UnknownStrategy(name.clone()))?;
+                if info.is_builtin_hypotheticals() {
+                    Ok(Box::new(self.build_hypotheticals(registry)?) as _)
+                } else {
+                    Ok(info.create())
+                }
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Solver::with_budget(strategies, self.budget.to_budget()?))
+    }
+
+    fn build_hypotheticals(
+        &self,
+        registry: &StrategyRegistry,
+    ) -> Result<Hypotheticals, ConfigError> {
+        let config = &self.hypotheticals;
+        if config.depth == 0 {
+            return Err(ConfigError::InvalidDepth);
+        }
+
+        let ordering = builtin_orderings()
+            .into_iter()
+            .find(|ordering| ordering.name() == config.ordering)
+            .ok_or_else(|| ConfigError::UnknownOrdering(config.ordering.clone()))?;
+
+        let options = HypotheticalsOptions {
+            ordering: Arc::clone(&ordering),
+            max_trials: config.max_trials,
+            learn_from_both_branches: config.learn_from_both_branches,
+            on_solution: config.on_solution,
+            transposition_table_capacity: config.transposition_table_capacity,
+            parallel: config.parallel,
+        };
+
+        // Look the strategies up front, since `Hypotheticals::nested` can't handle errors
+        let names = config.strategies.as_ref().unwrap_or(&self.strategies);
+        let mut infos = names
+            .iter()
+            .map(|name| {
+                registry
+                    .get(name)
+                    .ok_or_else(|| ConfigError::UnknownStrategy(name.clone()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if config.strategies.is_none() {
+            // The inner strategies default to the outer ones, besides `Hypotheticals` itself
+            infos.retain(|info| !info.is_builtin_hypotheticals());
+        }
+
+        Ok(Hypotheticals::nested(config.depth, options, || {
+            infos.iter().map(|info| info.create()).collect()
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::strategy::avoid_pools::AvoidPools;
+    use crate::Grid;
+
+    #[test]
+    fn test_profiles() {
+        // 5x5 Nurikabe Hard Puzzle ID: 9,690,008 from https://www.puzzle-nurikabe.com/
+        let grid = Grid::from_str("2..1.\n.....\n...3.\n....5\n.....\n").unwrap();
+
+        let results = SolverConfig::PROFILES.map(|name| {
+            let solver = SolverConfig::profile(name).unwrap().build().unwrap();
+            solver.solve(&mut grid.clone()).is_ok()
+        });
+        assert_eq!(results, [false, true, true, true, true]);
+        assert!(matches!(
+            SolverConfig::profile("expert"),
+            Err(ConfigError::UnknownProfile(_))
+        ));
+    }
+
+    #[test]
+    fn test_unknown_strategy() {
+        let mut config = SolverConfig::profile("full").unwrap();
+        config.hypotheticals.strategies = Some(vec!["Guess".to_string()]);
+        assert!(matches!(
+            config.build(),
+            Err(ConfigError::UnknownStrategy(name)) if name == "Guess"
+        ));
+    }
+
+    #[test]
+    fn test_custom_registry() {
+        let mut registry = StrategyRegistry::builtin();
+        registry.register("Basic", "Avoids pools.", || Box::new(AvoidPools));
+
+        let config = SolverConfig {
+            strategies: vec!["Basic".to_string(), "Hypotheticals".to_string()],
+            hypotheticals: HypotheticalsConfig::default(),
+            budget: BudgetConfig::default(),
+        };
+        assert!(config.build_with_registry(&registry).is_ok());
+        assert!(matches!(
+            config.build(),
+            Err(ConfigError::UnknownStrategy(name)) if name == "Basic"
+        ));
+    }
+
+    #[test]
+    fn test_override_hypotheticals() {
+        let mut config = SolverConfig::profile("full").unwrap();
+        // Invalid for the built-in strategy, but unused by the override
+        config.hypotheticals.depth = 0;
+        assert!(matches!(config.build(), Err(ConfigError::InvalidDepth)));
+
+        let mut registry = StrategyRegistry::new();
+        for info in StrategyRegistry::builtin().iter() {
+            if info.name() != "Hypotheticals" {
+                let (name, description) = (info.name().to_string(), info.description().to_string());
+                let info = info.clone();
+                registry.register(name, description, move || info.create());
+            }
+        }
+        assert!(matches!(
+            config.build_with_registry(&registry),
+            Err(ConfigError::UnknownStrategy(name)) if name == "Hypotheticals"
+        ));
+
+        registry.register("Hypotheticals", "Avoids pools.", || Box::new(AvoidPools));
+        assert!(config.build_with_registry(&registry).is_ok());
+    }
+
+    #[cfg(feature = "config")]
+    #[test]
+    fn test_from_toml() {
+        let config = SolverConfig::from_toml(
+            r#"
+            profile = "human-basic"
+
+            [hypotheticals]
+            depth = 2
+            ordering = "MostConstrained"
+            on_solution = "adopt-if-unique"
+
+            [budget]
+            time_limit_secs = 1.5
+            "#,
+        )
+        .unwrap();
+
+        let basic = SolverConfig::profile("human-basic").unwrap();
+        assert_eq!(config.strategies, basic.strategies);
+        assert_eq!(config.hypotheticals.depth, 2);
+        // Keys left out of the section are taken from the profile
+        assert_eq!(
+            config.hypotheticals.strategies,
+            basic.hypotheticals.strategies
+        );
+        assert!(config.hypotheticals.strategies.is_some());
+        assert_eq!(
+            config.hypotheticals.on_solution,
+            SolutionPolicy::AdoptIfUnique
+        );
+        assert_eq!(
+            config.budget.to_budget().unwrap().time_limit,
+            Some(Duration::from_millis(1500))
+        );
+        assert!(config.build().is_ok());
+
+        assert!(matches!(
+            SolverConfig::from_toml("strategies = [\"AvoidPools\"]\nmax_depth = 3"),
+            Err(ConfigError::Parse(_))
+        ));
+        assert!(matches!(
+            SolverConfig::from_toml("[budget]\ntime_limit_secs = -1.0"),
+            Err(ConfigError::InvalidTimeLimit(_))
+        ));
+    }
+
+    #[test]
+    fn test_invalid_time_limit() {
+        for secs in [-1.0, f64::NAN, f64::INFINITY, 1e30] {
+            let mut config = SolverConfig::profile("full").unwrap();
+            config.budget.time_limit_secs = Some(secs);
+            assert!(matches!(
+                config.build(),
+                Err(ConfigError::InvalidTimeLimit(_))
+            ));
+        }
+    }
+}
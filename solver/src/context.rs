@@ -0,0 +1,232 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::{Grid, SolverError};
+
+/// Limits on the work a solve may do. Once any limit is exceeded, the solve stops with
+/// [`SolverError::BudgetExceeded`], leaving the grid as far as it got.
+#[derive(Clone, Debug, Default)]
+pub struct Budget {
+    /// Point in time after which the solve stops.
+    pub deadline: Option<Instant>,
+    /// Maximum time the solve may take, measured from when it starts.
+    pub time_limit: Option<Duration>,
+    /// Maximum number of times a strategy may change the grid, including in nested solves.
+    pub max_applications: Option<usize>,
+    /// Maximum number of hypotheses that may be tried, at any depth.
+    pub max_hypotheses: Option<usize>,
+    /// Flag that can be set from another thread to stop the solve.
+    pub cancel: Option<Arc<AtomicBool>>,
+}
+
+/// A snapshot of how far a solve has come, passed to a [`ProgressReporter`].
+#[derive(Clone, Debug)]
+pub struct Progress {
+    /// Number of cells decided in the grid being solved, not counting hypotheses.
+    pub decided_cells: usize,
+    pub total_cells: usize,
+    /// Number of hypotheses the solve is currently nested in.
+    pub depth: usize,
+    /// Number of hypotheses tried so far, at any depth.
+    pub hypotheses: usize,
+    /// Number of times a strategy changed the grid so far, including in nested solves.
+    pub applications: usize,
+    pub elapsed: Duration,
+}
+
+impl Progress {
+    /// Returns the fraction of cells that have been decided, between 0 and 1.
+    pub fn fraction_decided(&self) -> f64 {
+        if self.total_cells == 0 {
+            1.0
+        } else {
+            self.decided_cells as f64 / self.total_cells as f64
+        }
+    }
+}
+
+/// Receives periodic progress events during a solve, e.g. to drive a progress bar or a health
+/// check. Called from the solving thread(s), so it should return quickly.
+pub trait ProgressReporter: Send + Sync {
+    fn report(&self, progress: &Progress);
+}
+
+impl<F: Fn(&Progress) + Send + Sync> ProgressReporter for F {
+    fn report(&self, progress: &Progress) {
+        self(progress)
+    }
+}
+
+/// State shared by a solve and all solves nested in it, such as the ones done by
+/// [`Hypotheticals`](crate::strategy::hypotheticals::Hypotheticals).
+pub struct SolveContext {
+    shared: Arc<SharedState>,
+    /// Number of hypotheses this (nested) solve is in
+    depth: usize,
+}
+
+struct SharedState {
+    budget: Budget,
+    start: Instant,
+    applications: AtomicUsize,
+    hypotheses: AtomicUsize,
+    decided_cells: AtomicUsize,
+    total_cells: AtomicUsize,
+    progress: Option<ProgressState>,
+}
+
+struct ProgressState {
+    reporter: Arc<dyn ProgressReporter>,
+    interval: Duration,
+    last_report: Mutex<Instant>,
+}
+
+impl Default for SolveContext {
+    fn default() -> Self {
+        Self::new(Budget::default())
+    }
+}
+
+impl SolveContext {
+    pub fn new(budget: Budget) -> Self {
+        Self {
+            shared: Arc::new(SharedState {
+                budget,
+                start: Instant::now(),
+                applications: AtomicUsize::new(0),
+                hypotheses: AtomicUsize::new(0),
+                decided_cells: AtomicUsize::new(0),
+                total_cells: AtomicUsize::new(0),
+                progress: None,
+            }),
+            depth: 0,
+        }
+    }
+
+    /// Reports progress to the given reporter at most once per `interval`, and once more when the
+    /// solve ends. Must be called before the context is used.
+    pub fn with_progress(
+        mut self,
+        reporter: Arc<dyn ProgressReporter>,
+        interval: Duration,
+    ) -> Self {
+        let shared = Arc::get_mut(&mut self.shared).expect("context is already in use");
+        shared.progress = Some(ProgressState {
+            reporter,
+            interval,
+            last_report: Mutex::new(shared.start),
+        });
+        self
+    }
+
+    /// Returns a context for a solve nested in a hypothesis, sharing the budget and counters.
+    pub fn nested(&self) -> Self {
+        Self {
+            shared: Arc::clone(&self.shared),
+            depth: self.depth + 1,
+        }
+    }
+
+    /// Returns the number of hypotheses this (nested) solve is in.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Returns the number of times a strategy changed the grid so far.
+    pub fn applications(&self) -> usize {
+        self.shared.applications.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of hypotheses tried so far.
+    pub fn hypotheses(&self) -> usize {
+        self.shared.hypotheses.load(Ordering::Relaxed)
+    }
+
+    /// Returns an error if the solve was cancelled or ran out of time. Also reports progress if it
+    /// is due.
+    pub fn check(&self) -> Result<(), SolverError> {
+        self.report_progress(false);
+
+        let budget = &self.shared.budget;
+        let is_cancelled = budget
+            .cancel
+            .as_ref()
+            .is_some_and(|cancel| cancel.load(Ordering::Relaxed));
+        let now = Instant::now();
+        let is_past_deadline = budget.deadline.is_some_and(|deadline| now >= deadline)
+            || budget
+                .time_limit
+                .is_some_and(|time_limit| now.duration_since(self.shared.start) >= time_limit);
+
+        if is_cancelled || is_past_deadline {
+            Err(SolverError::BudgetExceeded)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Counts a strategy changing the grid.
+    pub fn count_application(&self) -> Result<(), SolverError> {
+        let applications = self.shared.applications.fetch_add(1, Ordering::Relaxed) + 1;
+        Self::check_limit(applications, self.shared.budget.max_applications)?;
+        self.check()
+    }
+
+    /// Counts a hypothesis about to be tried.
+    pub fn count_hypothesis(&self) -> Result<(), SolverError> {
+        let hypotheses = self.shared.hypotheses.fetch_add(1, Ordering::Relaxed) + 1;
+        Self::check_limit(hypotheses, self.shared.budget.max_hypotheses)?;
+        self.check()
+    }
+
+    fn check_limit(count: usize, limit: Option<usize>) -> Result<(), SolverError> {
+        if limit.is_some_and(|limit| count > limit) {
+            Err(SolverError::BudgetExceeded)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Records how many cells of the grid are decided. Only grids outside of hypotheses count
+    /// towards progress.
+    pub(crate) fn update_decided_cells(&self, grid: &Grid) {
+        if self.depth == 0 && self.shared.progress.is_some() {
+            let decided_cells = grid.cells().filter(|cell| cell.state.is_some()).count();
+            let shared = &self.shared;
+            shared.decided_cells.store(decided_cells, Ordering::Relaxed);
+            shared
+                .total_cells
+                .store(grid.num_cells(), Ordering::Relaxed);
+        }
+    }
+
+    /// Reports progress if the interval has passed since the last report, or if `force` is set.
+    pub(crate) fn report_progress(&self, force: bool) {
+        let Some(progress) = &self.shared.progress else {
+            return;
+        };
+
+        let now = Instant::now();
+        {
+            // Another thread holding the lock is about to report anyway
+            let Ok(mut last_report) = progress.last_report.try_lock() else {
+                return;
+            };
+            if !force && now.duration_since(*last_report) < progress.interval {
+                return;
+            }
+            *last_report = now;
+        }
+
+        let shared = &self.shared;
+        progress.reporter.report(&Progress {
+            decided_cells: shared.decided_cells.load(Ordering::Relaxed),
+            total_cells: shared.total_cells.load(Ordering::Relaxed),
+            depth: self.depth,
+            hypotheses: self.hypotheses(),
+            applications: self.applications(),
+            elapsed: now.duration_since(shared.start),
+        });
+    }
+}
@@ -0,0 +1,357 @@
+//! Generates puzzles with a unique solution that a chosen solver configuration can solve, e.g. to
+//! produce puzzles of a reliable difficulty:
+//!
+//! ```
+//! use nurikabe::config::SolverConfig;
+//! use nurikabe::generator::{Generator, GeneratorConfig};
+//!
+//! // A 6x6 puzzle that needs `Confinement`, but not `Hypotheticals`
+//! let mut config = GeneratorConfig::new(6, 6, SolverConfig::profile("human-advanced").unwrap());
+//! config.required_strategies = vec!["Confinement".to_string()];
+//! let puzzle = Generator::new(config).unwrap().generate().unwrap();
+//! println!("{}", puzzle.clues.to_input_string());
+//! ```
+
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+use crate::config::{ConfigError, SolverConfig};
+use crate::strategy::hypotheticals::SolutionPolicy;
+use crate::{Coord, Grid, Solver, SolverLogger};
+
+/// Describes what kind of puzzles a [`Generator`] generates.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GeneratorConfig {
+    pub num_rows: usize,
+    pub num_cols: usize,
+    /// The solver every puzzle must be solvable with. Candidates that need strategies outside of
+    /// it are rejected. Solutions found by hypotheticals are only adopted once they are proven to
+    /// be unique, so that only puzzles with a unique solution are accepted.
+    pub solver: SolverConfig,
+    /// Names of strategies the solve must use at least once, e.g. so that puzzles aren't easier
+    /// than intended. Since the solver applies the first strategy that marks any cells, a strategy
+    /// listed after all simpler ones is only used when they are stuck.
+    pub required_strategies: Vec<String>,
+    /// Maximum number of cells of an island.
+    pub max_island_len: usize,
+    /// Number of candidates to try before giving up.
+    pub max_attempts: usize,
+    /// Seed of the random number generator. Generators with the same configuration generate the
+    /// same puzzles.
+    pub seed: u64,
+}
+
+impl GeneratorConfig {
+    pub fn new(num_rows: usize, num_cols: usize, solver: SolverConfig) -> Self {
+        Self {
+            num_rows,
+            num_cols,
+            solver,
+            required_strategies: vec![],
+            max_island_len: 9,
+            max_attempts: 1000,
+            seed: 0,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum GeneratorError {
+    Config(ConfigError),
+    /// A required strategy isn't one of the strategies of the solver, so no puzzle can use it.
+    UnusableStrategy(String),
+    /// None of the candidates met the requirements within the maximum number of attempts.
+    NoPuzzleFound,
+}
+
+impl Display for GeneratorError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GeneratorError::Config(err) => write!(f, "invalid solver config: {}", err),
+            GeneratorError::UnusableStrategy(name) => {
+                write!(f, "required strategy {:?} is not used by the solver", name)
+            }
+            GeneratorError::NoPuzzleFound => write!(f, "no puzzle found within the attempts"),
+        }
+    }
+}
+
+impl Error for GeneratorError {}
+
+/// A generated puzzle.
+#[derive(Clone)]
+pub struct Puzzle {
+    /// The grid with only the clues given
+    pub clues: Grid,
+    pub solution: Grid,
+    /// Names of the strategies the solver used, in the order they were first used
+    pub strategies_used: Vec<String>,
+}
+
+pub struct Generator {
+    config: GeneratorConfig,
+    solver: Solver,
+    rng: Rng,
+}
+
+impl Generator {
+    pub fn new(config: GeneratorConfig) -> Result<Self, GeneratorError> {
+        if let Some(name) = config
+            .required_strategies
+            .iter()
+            .find(|name| !config.solver.strategies.contains(name))
+        {
+            return Err(GeneratorError::UnusableStrategy(name.clone()));
+        }
+
+        let solver = build_checker(&config.solver).map_err(GeneratorError::Config)?;
+
+        Ok(Self {
+            rng: Rng::new(config.seed),
+            config,
+            solver,
+        })
+    }
+
+    /// Generates a puzzle. Calling this again generates a different puzzle.
+    pub fn generate(&mut self) -> Result<Puzzle, GeneratorError> {
+        for _ in 0..self.config.max_attempts {
+            if let Some(puzzle) = self.random_solution().and_then(|white| self.check(&white)) {
+                return Ok(puzzle);
+            }
+        }
+        Err(GeneratorError::NoPuzzleFound)
+    }
+
+    /// Places a clue in every island of the solution, and checks that the solver solves the
+    /// resulting puzzle using the required strategies.
+    fn check(&mut self, white: &[bool]) -> Option<Puzzle> {
+        let template = Grid::new(self.config.num_rows, self.config.num_cols, []);
+
+        let mut clues = vec![];
+        let mut seen = HashSet::new();
+        for (coord, _) in template.iter() {
+            if white[index(&template, coord)] && !seen.contains(&coord) {
+                let island = component(&template, white, coord, true);
+                seen.extend(island.iter().copied());
+                let clue = island[self.rng.below(island.len())];
+                clues.push((clue, island.len()));
+            }
+        }
+        let clues = Grid::new(self.config.num_rows, self.config.num_cols, clues);
+
+        let required = &self.config.required_strategies;
+        solve_expected(&self.solver, clues, white).filter(|puzzle| {
+            required
+                .iter()
+                .all(|name| puzzle.strategies_used.contains(name))
+        })
+    }
+
+    /// Returns a random valid arrangement of white cells, where `true` means white: the black
+    /// cells are connected and contain no pools, and no island is too large. Starts from an
+    /// all-black grid and whitens cells of the remaining pools one by one. Returns `None` if a pool
+    /// can't be broken up.
+    fn random_solution(&mut self) -> Option<Vec<bool>> {
+        let template = Grid::new(self.config.num_rows, self.config.num_cols, []);
+        let mut white = vec![false; self.config.num_rows * self.config.num_cols];
+
+        loop {
+            let pools = template
+                .pool_windows()
+                .filter(|pool| pool.iter().all(|&coord| !white[index(&template, coord)]))
+                .collect::<Vec<_>>();
+            if pools.is_empty() {
+                return Some(white);
+            }
+
+            let mut pool = pools[self.rng.below(pools.len())].to_vec();
+            self.rng.shuffle(&mut pool);
+            let coord = pool
+                .into_iter()
+                .find(|&coord| self.can_whiten(&template, &white, coord))?;
+            white[index(&template, coord)] = true;
+        }
+    }
+
+    /// Returns `true` if whitening the black cell keeps the black cells connected, and doesn't
+    /// create an island that is too large.
+    fn can_whiten(&self, template: &Grid, white: &[bool], coord: Coord) -> bool {
+        let mut white = white.to_vec();
+        white[index(template, coord)] = true;
+
+        if component(template, &white, coord, true).len() > self.config.max_island_len {
+            return false;
+        }
+
+        is_black_connected(template, &white)
+    }
+}
+
+/// Builds the solver that checks candidates. Solutions found by hypotheticals are only adopted once
+/// they are proven to be unique.
+pub(crate) fn build_checker(config: &SolverConfig) -> Result<Solver, ConfigError> {
+    let mut config = config.clone();
+    config.hypotheticals.on_solution = SolutionPolicy::AdoptIfUnique;
+    config.build()
+}
+
+/// Solves the clues, and returns the puzzle if the solver finds the expected solution, where `true`
+/// means white.
+pub(crate) fn solve_expected(solver: &Solver, clues: Grid, white: &[bool]) -> Option<Puzzle> {
+    let mut solution = clues.clone();
+    let mut logger = UsedStrategies::default();
+    solver.solve_with_logger(&mut solution, &mut logger).ok()?;
+
+    // The strategies only make sound deductions, so the solution should be the one the clues were
+    // derived from. Check it anyway rather than return a puzzle with the wrong solution.
+    let matches = solution.iter().all(|(coord, cell)| {
+        cell.state
+            .is_some_and(|state| state.is_black() != white[index(&clues, coord)])
+    });
+    matches.then_some(Puzzle {
+        clues,
+        solution,
+        strategies_used: logger.0,
+    })
+}
+
+pub(crate) fn index(template: &Grid, coord: Coord) -> usize {
+    coord.row * template.num_cols + coord.col
+}
+
+/// Returns `true` if the black cells are connected.
+pub(crate) fn is_black_connected(template: &Grid, white: &[bool]) -> bool {
+    let mut black = template
+        .iter()
+        .map(|(coord, _)| coord)
+        .filter(|&coord| !white[index(template, coord)]);
+    match black.next() {
+        Some(start) => component(template, white, start, false).len() == black.count() + 1,
+        None => true,
+    }
+}
+
+/// Returns the cells connected to `start` through cells with the given color.
+pub(crate) fn component(
+    template: &Grid,
+    white: &[bool],
+    start: Coord,
+    is_white: bool,
+) -> Vec<Coord> {
+    let mut cells = vec![start];
+    let mut seen = HashSet::from([start]);
+    let mut next = 0;
+    while let Some(&coord) = cells.get(next) {
+        next += 1;
+        for adj_coord in template.valid_neighbors(coord) {
+            if white[index(template, adj_coord)] == is_white && seen.insert(adj_coord) {
+                cells.push(adj_coord);
+            }
+        }
+    }
+    cells
+}
+
+/// Records which strategies a solve used.
+#[derive(Default)]
+struct UsedStrategies(Vec<String>);
+
+impl SolverLogger for &mut UsedStrategies {
+    fn before_apply(&mut self, _grid: &Grid) {}
+
+    fn strategy_applied(&mut self, _grid: &Grid, strategy_name: &str) {
+        if !self.0.iter().any(|name| name == strategy_name) {
+            self.0.push(strategy_name.to_string());
+        }
+    }
+
+    fn no_strategy_applies(&mut self, _grid: &Grid) {}
+}
+
+/// A small, seedable random number generator (SplitMix64), so that generated puzzles are
+/// reproducible without depending on a random number crate.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a number in `0..n`.
+    fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i + 1));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Generator, GeneratorConfig, GeneratorError};
+    use crate::config::SolverConfig;
+
+    fn basic() -> SolverConfig {
+        let mut config = SolverConfig::profile("human-basic").unwrap();
+        config.strategies = ["CompleteIslands", "SingleLiberties", "DualLiberties"]
+            .map(String::from)
+            .to_vec();
+        config
+    }
+
+    #[test]
+    fn test_generate() {
+        let mut generator = Generator::new(GeneratorConfig::new(7, 7, basic())).unwrap();
+        let puzzle = generator.generate().unwrap();
+        assert!(puzzle.solution.is_complete());
+
+        // The solver of the profile solves the puzzle on its own
+        let mut grid = puzzle.clues.clone();
+        basic().build().unwrap().solve(&mut grid).unwrap();
+        assert_eq!(grid.to_input_string(), puzzle.solution.to_input_string());
+
+        let next = generator.generate().unwrap();
+        assert_ne!(next.clues.to_input_string(), puzzle.clues.to_input_string());
+    }
+
+    #[test]
+    fn test_required_strategies() {
+        let mut config =
+            GeneratorConfig::new(6, 6, SolverConfig::profile("human-advanced").unwrap());
+        config.required_strategies = vec!["Confinement".to_string()];
+        let puzzle = Generator::new(config).unwrap().generate().unwrap();
+        assert!(puzzle.strategies_used.contains(&"Confinement".to_string()));
+
+        // Too hard for the basic strategies
+        let mut grid = puzzle.clues.clone();
+        assert!(SolverConfig::profile("human-basic")
+            .unwrap()
+            .build()
+            .unwrap()
+            .solve(&mut grid)
+            .is_err());
+    }
+
+    #[test]
+    fn test_unusable_strategy() {
+        let mut config = GeneratorConfig::new(5, 5, basic());
+        config.required_strategies = vec!["Hypotheticals".to_string()];
+        assert!(matches!(
+            Generator::new(config),
+            Err(GeneratorError::UnusableStrategy(name)) if name == "Hypotheticals"
+        ));
+    }
+}
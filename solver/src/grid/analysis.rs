@@ -1,5 +1,6 @@
 use std::collections::{HashSet, VecDeque};
 
+use crate::grid::cache::ConfinementFlood;
 use crate::grid::Region;
 use crate::{Coord, Grid, RegionID, SolverError, State};
 
@@ -10,22 +11,11 @@ impl Grid {
         coord: Coord,
         assume_black: impl IntoIterator<Item = Coord>,
     ) -> bool {
-        if self.cell(coord).state != None {
+        if self.cell(coord).state.is_some() || self.is_void(coord) {
             return false;
         }
 
-        // The maximum size a white region can be if we still want to be able to join it with a numbered region
-        let max_white_region_len = self
-            .regions()
-            .filter_map(|region| {
-                if let State::Numbered(max_region_len) = region.state {
-                    Some(max_region_len.saturating_sub(region.len()))
-                } else {
-                    None
-                }
-            })
-            .max()
-            .unwrap_or(0);
+        let max_white_region_len = self.max_white_region_len();
 
         let mut explored = HashSet::from([coord]);
         explored.extend(assume_black);
@@ -36,7 +26,7 @@ impl Grid {
             let mut adj_numbered_regions = HashSet::new();
             let mut adj_white_regions = HashSet::new();
             for adj_coord in self.valid_neighbors(cur_coord) {
-                if let Some(adj_region_id) = self.cell(adj_coord).region {
+                if let Some(adj_region_id) = self.region_id(adj_coord) {
                     match self.region(adj_region_id).unwrap().state {
                         State::Numbered(_) => {
                             adj_numbered_regions.insert(adj_region_id);
@@ -67,7 +57,8 @@ impl Grid {
                 // Path reached a numbered region
                 let region_id = *adj_numbered_regions.iter().next().unwrap();
                 let region = self.region(region_id).unwrap();
-                if let State::Numbered(max_region_len) = region.state {
+                if let State::Numbered(number) = region.state {
+                    let max_region_len = self.max_island_len(number);
                     // Could the current path be fused to the numbered region?
                     if extra_region_len + region.len() <= max_region_len {
                         // Current path might be reachable from the numbered region
@@ -85,7 +76,7 @@ impl Grid {
                 // Path reached a white region
                 // Could the region formed by fusing the current path to the
                 // adjacent white regions ever be connected to a numbered region?
-                if extra_region_len + 1 <= max_white_region_len {
+                if extra_region_len < max_white_region_len {
                     // Current path might be reachable through the adjacent white regions
                     return false;
                 } else {
@@ -105,22 +96,49 @@ impl Grid {
         true
     }
 
-    pub(crate) fn is_region_confined(
+    /// The maximum size a white region can be if we still want to be able to join it with a numbered
+    /// region.
+    pub(crate) fn compute_max_white_region_len(&self) -> usize {
+        self.regions()
+            .filter_map(|region| {
+                if let State::Numbered(number) = region.state {
+                    Some(self.max_island_len(number).saturating_sub(region.len()))
+                } else {
+                    None
+                }
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Floods outwards from a region through the cells it could grow into, to determine whether it
+    /// is confined, i.e. it can't grow to its required size.
+    pub(crate) fn confinement_flood(
         &self,
         region_id: RegionID,
         assume_visited: impl IntoIterator<Item = Coord>,
+    ) -> ConfinementFlood {
+        let mut visited = HashSet::new();
+        let result = self.flood_region(region_id, assume_visited, &mut visited);
+        ConfinementFlood { result, visited }
+    }
+
+    fn flood_region(
+        &self,
+        region_id: RegionID,
+        assume_visited: impl IntoIterator<Item = Coord>,
+        visited: &mut HashSet<Coord>,
     ) -> Result<bool, SolverError> {
         let region = self.region(region_id).unwrap();
 
         let mut open = VecDeque::from_iter(region.unknowns.iter().copied());
 
-        let mut visited = HashSet::new();
-        visited.extend(region.coords.iter().copied());
+        visited.extend(self.region_coords(region));
         visited.extend(assume_visited);
 
         // Set of cells that may connect to the region
         let mut closed = HashSet::new();
-        closed.extend(region.coords.iter().copied());
+        closed.extend(self.region_coords(region));
 
         while let Some(coord) = open.pop_front() {
             if !visited.insert(coord) {
@@ -132,8 +150,7 @@ impl Grid {
             }
 
             let other_region = self
-                .cell(coord)
-                .region
+                .region_id(coord)
                 .and_then(|region_id| self.region(region_id));
 
             match region.state {
@@ -152,7 +169,7 @@ impl Grid {
                     None => {
                         if self
                             .valid_neighbors(coord)
-                            .filter_map(|adj_coord| self.cell(adj_coord).region)
+                            .filter_map(|adj_coord| self.region_id(adj_coord))
                             .filter(|adj_region_id| {
                                 self.region(*adj_region_id)
                                     .map(|adj_region| adj_region.state.is_numbered())
@@ -193,8 +210,8 @@ impl Grid {
 
             // Consume the region/cell
             if let Some(other_region) = other_region {
-                closed.extend(other_region.coords.iter().copied());
-                visited.extend(other_region.coords.iter().copied());
+                closed.extend(self.region_coords(other_region));
+                visited.extend(self.region_coords(other_region));
                 open.extend(other_region.unknowns.iter().copied());
             } else {
                 closed.insert(coord);
@@ -206,26 +223,96 @@ impl Grid {
         Ok(self.is_region_like_incomplete(region.state, closed.len()))
     }
 
+    /// Returns, for every cell, the number of numbered regions that could still grow to include
+    /// it. Paths are only counted through unknown cells, so this is a rough estimate.
+    pub(crate) fn compute_possible_owner_counts(&self) -> Box<[usize]> {
+        let mut owner_counts = vec![0; self.cells.len()].into_boxed_slice();
+
+        for (region_id, region) in self.regions_iter() {
+            let State::Numbered(number) = region.state else {
+                continue;
+            };
+            let remaining_len = self.max_island_len(number).saturating_sub(region.len());
+
+            let mut explored = HashSet::<Coord>::from_iter(region.unknowns.iter().copied());
+            let mut queue = VecDeque::from_iter(region.unknowns.iter().map(|&coord| (coord, 1)));
+
+            while let Some((coord, depth)) = queue.pop_front() {
+                if depth > remaining_len {
+                    continue;
+                }
+
+                let is_adjacent_to_other_numbered_region = self
+                    .valid_neighbors(coord)
+                    .filter_map(|adj_coord| self.region_id(adj_coord))
+                    .any(|adj_region_id| {
+                        adj_region_id != region_id
+                            && self.region(adj_region_id).unwrap().state.is_numbered()
+                    });
+                if is_adjacent_to_other_numbered_region {
+                    continue;
+                }
+
+                owner_counts[self.coord_to_index(coord)] += 1;
+
+                for adj_coord in self.valid_unknown_neighbors(coord) {
+                    if explored.insert(adj_coord) {
+                        queue.push_back((adj_coord, depth + 1));
+                    }
+                }
+            }
+        }
+
+        owner_counts
+    }
+
     pub(crate) fn is_region_incomplete(&self, region: &Region) -> bool {
         self.is_region_like_incomplete(region.state, region.len())
     }
 
+    /// Returns `true` if a region with the given state and size must still grow. A black region
+    /// must grow until it contains all black cells; if their number is unknown, it must at least
+    /// grow to contain the black cells marked so far.
     pub(crate) fn is_region_like_incomplete(&self, region_state: State, region_len: usize) -> bool {
         match region_state {
-            State::White => true,
-            State::Black => region_len < self.total_black_cells,
-            State::Numbered(number) => region_len < number,
+            State::White => self.rules.white_regions_need_numbers(),
+            State::Black if !self.rules.is_wall_connected() => false,
+            State::Black => {
+                region_len
+                    < self
+                        .total_black_cells
+                        .unwrap_or_else(|| self.num_black_cells())
+            }
+            State::Numbered(Some(number)) => region_len < number,
+            // An island with a `?` clue can have any size, including its current one
+            State::Numbered(None) => false,
         }
     }
 
+    /// Returns `true` if whether a black region is complete depends on black cells anywhere on the
+    /// grid, because the total number of black cells is unknown (e.g. due to a `?` clue). Marking a
+    /// cell can then affect black regions far away from it.
+    pub(crate) fn is_black_region_len_unknown(&self) -> bool {
+        self.rules.is_wall_connected() && self.total_black_cells.is_none()
+    }
+
     /// Returns `true` if the region is larger than should be possible.
     pub(crate) fn is_region_overfilled(&self, region: &Region) -> bool {
         match region.state {
-            State::White => false,
-            State::Black => region.len() > self.total_black_cells,
-            State::Numbered(number) => region.len() > number,
+            State::White | State::Numbered(None) => false,
+            State::Black => self
+                .total_black_cells
+                .is_some_and(|total_black_cells| region.len() > total_black_cells),
+            State::Numbered(Some(number)) => region.len() > number,
         }
     }
+
+    pub(crate) fn compute_num_black_cells(&self) -> usize {
+        self.regions()
+            .filter(|region| region.state.is_black())
+            .map(|region| region.len())
+            .sum()
+    }
 }
 
 #[cfg(test)]
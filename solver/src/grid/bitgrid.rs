@@ -0,0 +1,967 @@
+use std::sync::Arc;
+
+use super::topology::Adjacency;
+use crate::{Coord, Grid, Layout, RuleSet, SolverError, State, Topology};
+
+/// A fixed-size set of bits, stored in 64-bit words.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct BitSet {
+    words: Box<[u64]>,
+}
+
+impl BitSet {
+    fn new(len: usize) -> Self {
+        Self {
+            words: vec![0; len.div_ceil(64)].into_boxed_slice(),
+        }
+    }
+
+    fn get(&self, index: usize) -> bool {
+        self.words[index / 64] & (1 << (index % 64)) != 0
+    }
+
+    fn set(&mut self, index: usize) {
+        self.words[index / 64] |= 1 << (index % 64);
+    }
+
+    fn unset(&mut self, index: usize) {
+        self.words[index / 64] &= !(1 << (index % 64));
+    }
+
+    /// Returns the number of bits in the set, including unset bits.
+    fn len(&self) -> usize {
+        self.words.len() * 64
+    }
+
+    fn is_empty(&self) -> bool {
+        self.words.iter().all(|&word| word == 0)
+    }
+
+    fn is_subset(&self, other: &Self) -> bool {
+        self.words
+            .iter()
+            .zip(other.words.iter())
+            .all(|(&a, &b)| a & !b == 0)
+    }
+
+    fn zip_with(&self, other: &Self, f: impl Fn(u64, u64) -> u64) -> Self {
+        Self {
+            words: self
+                .words
+                .iter()
+                .zip(other.words.iter())
+                .map(|(&a, &b)| f(a, b))
+                .collect(),
+        }
+    }
+
+    fn and(&self, other: &Self) -> Self {
+        self.zip_with(other, |a, b| a & b)
+    }
+
+    fn or(&self, other: &Self) -> Self {
+        self.zip_with(other, |a, b| a | b)
+    }
+
+    fn and_not(&self, other: &Self) -> Self {
+        self.zip_with(other, |a, b| a & !b)
+    }
+
+    /// Moves every bit from index `i` to index `i + shift`, dropping bits that fall off the end.
+    fn shift_up(&self, shift: usize) -> Self {
+        let (word_shift, bit_shift) = (shift / 64, shift % 64);
+        let mut words = vec![0; self.words.len()].into_boxed_slice();
+        for (index, word) in words.iter_mut().enumerate().skip(word_shift) {
+            let source = index - word_shift;
+            *word = self.words[source] << bit_shift;
+            if bit_shift > 0 && source > 0 {
+                *word |= self.words[source - 1] >> (64 - bit_shift);
+            }
+        }
+        Self { words }
+    }
+
+    /// Moves every bit from index `i` to index `i - shift`, dropping bits that fall off the start.
+    fn shift_down(&self, shift: usize) -> Self {
+        let (word_shift, bit_shift) = (shift / 64, shift % 64);
+        let len = self.words.len();
+        let mut words = vec![0; len].into_boxed_slice();
+        for (index, word) in words.iter_mut().enumerate() {
+            let source = index + word_shift;
+            if source >= len {
+                break;
+            }
+            *word = self.words[source] >> bit_shift;
+            if bit_shift > 0 && source + 1 < len {
+                *word |= self.words[source + 1] << (64 - bit_shift);
+            }
+        }
+        Self { words }
+    }
+
+    fn first(&self) -> Option<usize> {
+        self.iter().next()
+    }
+
+    fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.words.iter().enumerate().flat_map(|(index, &word)| {
+            let mut word = word;
+            std::iter::from_fn(move || {
+                if word == 0 {
+                    return None;
+                }
+                let bit = word.trailing_zeros() as usize;
+                word &= word - 1;
+                Some(index * 64 + bit)
+            })
+        })
+    }
+}
+
+/// A connected group of white cells, including the numbered cells it contains.
+struct WhiteComponent {
+    len: usize,
+    number: Option<usize>,
+    num_numbers: usize,
+}
+
+/// The cells along the edges of a wrapping board, which move to the opposite edge when the board
+/// is shifted across them.
+#[derive(Clone, Debug)]
+struct Edges {
+    first_row: BitSet,
+    last_row: BitSet,
+    first_col: BitSet,
+    last_col: BitSet,
+}
+
+/// The rows of a hex board. The neighbors of a cell depend on whether its row is shifted.
+#[derive(Clone, Debug)]
+struct HexRows {
+    even: BitSet,
+    /// Rows that are shifted right by half a cell
+    odd: BitSet,
+}
+
+/// Bitboard representation of a grid's cell states, so that whole-grid checks (pools, wall
+/// connectivity, reachability) can run as word-parallel operations.
+///
+/// Each row is padded with an always-empty guard column, so that shifting a bitset by one cell
+/// never moves a bit onto the next or previous row.
+#[derive(Clone, Debug)]
+pub struct BitGrid {
+    num_rows: usize,
+    num_cols: usize,
+    stride: usize,
+    /// Cells that are part of the board
+    board: BitSet,
+    topology: Arc<dyn Topology>,
+    /// Neighbors and pools of the cells, indexed like the cells of the grid
+    adjacency: Arc<Adjacency>,
+    /// Whether neighbors and pools can be found by shifting bitsets, i.e. the topology is the
+    /// built-in [`Board`](crate::Board)
+    shifts: bool,
+    /// Edges of the board, if it wraps around
+    edges: Option<Edges>,
+    /// Rows of the board, if it has a hex layout
+    hex: Option<HexRows>,
+    /// Rules of the grid the bitboard was created from, so that converting it back keeps them
+    rules: Arc<dyn RuleSet>,
+    black: BitSet,
+    /// White cells, including numbered cells
+    white: BitSet,
+    numbers: Vec<(Coord, Option<usize>)>,
+}
+
+impl BitGrid {
+    fn new_empty(
+        topology: Arc<dyn Topology>,
+        adjacency: Arc<Adjacency>,
+        rules: Arc<dyn RuleSet>,
+    ) -> Self {
+        let (num_rows, num_cols) = (topology.num_rows(), topology.num_cols());
+        let stride = num_cols + 1;
+        let len = num_rows * stride;
+
+        let mut board = BitSet::new(len);
+        for row in 0..num_rows {
+            for col in 0..num_cols {
+                board.set(row * stride + col);
+            }
+        }
+
+        Self {
+            num_rows,
+            num_cols,
+            stride,
+            board,
+            shifts: topology.as_board().is_some(),
+            topology,
+            adjacency,
+            edges: None,
+            hex: None,
+            rules,
+            black: BitSet::new(len),
+            white: BitSet::new(len),
+            numbers: vec![],
+        }
+    }
+
+    pub fn num_rows(&self) -> usize {
+        self.num_rows
+    }
+
+    pub fn num_cols(&self) -> usize {
+        self.num_cols
+    }
+
+    fn coord_to_index(&self, coord: Coord) -> usize {
+        coord.row * self.stride + coord.col
+    }
+
+    fn index_to_coord(&self, index: usize) -> Coord {
+        Coord::new(index / self.stride, index % self.stride)
+    }
+
+    pub fn is_black(&self, coord: Coord) -> bool {
+        self.black.get(self.coord_to_index(coord))
+    }
+
+    pub fn is_white(&self, coord: Coord) -> bool {
+        self.white.get(self.coord_to_index(coord))
+    }
+
+    pub fn is_unknown(&self, coord: Coord) -> bool {
+        self.unknown().get(self.coord_to_index(coord))
+    }
+
+    /// Returns `true` if the cell lies outside the board.
+    pub fn is_void(&self, coord: Coord) -> bool {
+        !self.board.get(self.coord_to_index(coord))
+    }
+
+    fn unknown(&self) -> BitSet {
+        self.board.and_not(&self.black).and_not(&self.white)
+    }
+
+    /// Makes the edges of the board wrap around.
+    fn set_wrap(&mut self) {
+        let len = self.num_rows * self.stride;
+        let mut edges = Edges {
+            first_row: BitSet::new(len),
+            last_row: BitSet::new(len),
+            first_col: BitSet::new(len),
+            last_col: BitSet::new(len),
+        };
+        for col in 0..self.num_cols {
+            edges.first_row.set(col);
+            edges.last_row.set((self.num_rows - 1) * self.stride + col);
+        }
+        for row in 0..self.num_rows {
+            edges.first_col.set(row * self.stride);
+            edges.last_col.set(row * self.stride + self.num_cols - 1);
+        }
+        self.edges = Some(edges);
+    }
+
+    /// Arranges the cells in staggered hex rows.
+    fn set_hex(&mut self) {
+        let len = self.num_rows * self.stride;
+        let mut hex = HexRows {
+            even: BitSet::new(len),
+            odd: BitSet::new(len),
+        };
+        for row in 0..self.num_rows {
+            let rows = if row % 2 == 0 {
+                &mut hex.even
+            } else {
+                &mut hex.odd
+            };
+            for col in 0..self.num_cols {
+                rows.set(row * self.stride + col);
+            }
+        }
+        self.hex = Some(hex);
+    }
+
+    /// Moves every cell in the set one column to the left.
+    fn move_left(&self, set: &BitSet) -> BitSet {
+        let moved = set.shift_down(1);
+        match &self.edges {
+            Some(edges) => moved.or(&set.and(&edges.first_col).shift_up(self.num_cols - 1)),
+            None => moved,
+        }
+        .and(&self.board)
+    }
+
+    /// Moves every cell in the set one column to the right.
+    fn move_right(&self, set: &BitSet) -> BitSet {
+        let moved = set.shift_up(1);
+        match &self.edges {
+            Some(edges) => moved.or(&set.and(&edges.last_col).shift_down(self.num_cols - 1)),
+            None => moved,
+        }
+        .and(&self.board)
+    }
+
+    /// Moves every cell in the set one row up.
+    fn move_up(&self, set: &BitSet) -> BitSet {
+        let moved = set.shift_down(self.stride);
+        match &self.edges {
+            Some(edges) => moved.or(&set
+                .and(&edges.first_row)
+                .shift_up((self.num_rows - 1) * self.stride)),
+            None => moved,
+        }
+        .and(&self.board)
+    }
+
+    /// Moves every cell in the set one row down.
+    fn move_down(&self, set: &BitSet) -> BitSet {
+        let moved = set.shift_up(self.stride);
+        match &self.edges {
+            Some(edges) => moved.or(&set
+                .and(&edges.last_row)
+                .shift_down((self.num_rows - 1) * self.stride)),
+            None => moved,
+        }
+        .and(&self.board)
+    }
+
+    /// Returns the cells adjacent to any cell in the given set.
+    fn neighbors(&self, set: &BitSet) -> BitSet {
+        if !self.shifts {
+            let mut neighbors = BitSet::new(set.len());
+            for index in set.iter() {
+                for adj_index in self.neighbor_indices(index) {
+                    neighbors.set(adj_index);
+                }
+            }
+            return neighbors;
+        }
+
+        // On hex boards, a cell also neighbors the cells above and below the cell next to it
+        // (on the left in even rows, on the right in odd rows).
+        let vertical = match &self.hex {
+            Some(hex) => set
+                .or(&self.move_left(&set.and(&hex.even)))
+                .or(&self.move_right(&set.and(&hex.odd))),
+            None => set.clone(),
+        };
+        self.move_left(set)
+            .or(&self.move_right(set))
+            .or(&self.move_up(&vertical))
+            .or(&self.move_down(&vertical))
+    }
+
+    /// Returns the cells whose neighbor below and to the left (on hex boards) is in the set.
+    fn pull_below_left(&self, set: &BitSet, hex: &HexRows) -> BitSet {
+        let above = self.move_up(set);
+        self.move_right(&above)
+            .and(&hex.even)
+            .or(&above.and(&hex.odd))
+    }
+
+    /// Returns the cells whose neighbor below and to the right (on hex boards) is in the set.
+    fn pull_below_right(&self, set: &BitSet, hex: &HexRows) -> BitSet {
+        let above = self.move_up(set);
+        above
+            .and(&hex.even)
+            .or(&self.move_left(&above).and(&hex.odd))
+    }
+
+    /// Returns the neighbors below and to the left (on hex boards) of the cells in the set.
+    fn push_below_left(&self, set: &BitSet, hex: &HexRows) -> BitSet {
+        self.move_down(&self.move_left(&set.and(&hex.even)).or(&set.and(&hex.odd)))
+    }
+
+    /// Returns the neighbors below and to the right (on hex boards) of the cells in the set.
+    fn push_below_right(&self, set: &BitSet, hex: &HexRows) -> BitSet {
+        self.move_down(&set.and(&hex.even).or(&self.move_right(&set.and(&hex.odd))))
+    }
+
+    /// Returns the indices of the cells adjacent to the cell with the given index.
+    fn neighbor_indices(&self, index: usize) -> impl Iterator<Item = usize> + '_ {
+        let coord = self.index_to_coord(index);
+        self.adjacency.neighbors[coord.row * self.num_cols + coord.col]
+            .iter()
+            .map(|&adj_coord| self.coord_to_index(adj_coord))
+    }
+
+    /// Returns the cells connected to `seed` through cells in `passable`.
+    fn flood(&self, seed: BitSet, passable: &BitSet) -> BitSet {
+        let mut filled = seed;
+        loop {
+            let next = filled.or(&self.neighbors(&filled).and(passable));
+            if next == filled {
+                return filled;
+            }
+            filled = next;
+        }
+    }
+
+    /// Returns the 2x2 windows whose four cells are each in the corresponding set. A window is
+    /// identified by the index of its top-left cell.
+    fn windows(
+        &self,
+        top_left: &BitSet,
+        top_right: &BitSet,
+        bottom_left: &BitSet,
+        bottom_right: &BitSet,
+    ) -> BitSet {
+        top_left
+            .and(&self.move_left(top_right))
+            .and(&self.move_up(bottom_left))
+            .and(&self.move_up(&self.move_left(bottom_right)))
+    }
+
+    /// Returns the hex triangles (the three cells around a vertex) whose cells are each in the
+    /// corresponding set. Triangles pointing down consist of a cell, its right neighbor and the
+    /// neighbor they share below; triangles pointing up of a cell and its two neighbors below.
+    /// Both are identified by the index of their first cell.
+    fn triangles(
+        &self,
+        hex: &HexRows,
+        first: &BitSet,
+        second: &BitSet,
+        below_right: &BitSet,
+    ) -> (BitSet, BitSet) {
+        let first = first.and(&self.pull_below_right(below_right, hex));
+        (
+            first.and(&self.move_left(second)),
+            first.and(&self.pull_below_left(second, hex)),
+        )
+    }
+
+    /// Returns `true` if there is a pool of black cells.
+    pub fn has_pool(&self) -> bool {
+        let black = &self.black;
+        if !self.shifts {
+            return self.adjacency.pools.iter().any(|pool| {
+                pool.iter()
+                    .all(|&coord| black.get(self.coord_to_index(coord)))
+            });
+        }
+
+        match &self.hex {
+            Some(hex) => {
+                let (down, up) = self.triangles(hex, black, black, black);
+                !down.is_empty() || !up.is_empty()
+            }
+            None => !self.windows(black, black, black, black).is_empty(),
+        }
+    }
+
+    /// Returns the unknown cells that would complete a pool of black cells, in row-major order.
+    pub fn pool_completions(&self) -> Vec<Coord> {
+        let black = &self.black;
+        let unknown = &self.unknown();
+
+        if !self.shifts {
+            let mut completions = BitSet::new(unknown.len());
+            for pool in self.adjacency.pools.iter() {
+                let mut not_black = pool
+                    .iter()
+                    .map(|&coord| self.coord_to_index(coord))
+                    .filter(|&index| !black.get(index));
+                if let (Some(index), None) = (not_black.next(), not_black.next()) {
+                    if unknown.get(index) {
+                        completions.set(index);
+                    }
+                }
+            }
+            return completions
+                .iter()
+                .map(|index| self.index_to_coord(index))
+                .collect();
+        }
+
+        let completions = match &self.hex {
+            Some(hex) => {
+                let (down_1, up_1) = self.triangles(hex, unknown, black, black);
+                let (down_2, up_2) = self.triangles(hex, black, unknown, black);
+                let (down_3, up_3) = self.triangles(hex, black, black, unknown);
+                down_1
+                    .or(&up_1)
+                    .or(&self.move_right(&down_2))
+                    .or(&self.push_below_left(&up_2, hex))
+                    .or(&self.push_below_right(&down_3.or(&up_3), hex))
+            }
+            None => self
+                .windows(unknown, black, black, black)
+                .or(&self.move_right(&self.windows(black, unknown, black, black)))
+                .or(&self.move_down(&self.windows(black, black, unknown, black)))
+                .or(&self.move_down(&self.move_right(&self.windows(black, black, black, unknown)))),
+        };
+
+        completions
+            .iter()
+            .map(|index| self.index_to_coord(index))
+            .collect()
+    }
+
+    /// Returns `true` if all black cells can still be connected through unknown cells.
+    pub fn is_wall_connectable(&self) -> bool {
+        let Some(start) = self.black.first() else {
+            return true;
+        };
+
+        let mut seed = BitSet::new(self.num_rows * self.stride);
+        seed.set(start);
+        let reachable = self.flood(seed, &self.black.or(&self.unknown()));
+        self.black.is_subset(&reachable)
+    }
+
+    /// Splits the white cells into connected components. Returns the component index of each cell
+    /// (`usize::MAX` for non-white cells) and the components themselves.
+    fn white_components(&self) -> (Vec<usize>, Vec<WhiteComponent>) {
+        let mut labels = vec![usize::MAX; self.num_rows * self.stride];
+        let mut components = vec![];
+
+        let mut remaining = self.white.clone();
+        while let Some(start) = remaining.first() {
+            let mut seed = BitSet::new(labels.len());
+            seed.set(start);
+            let component = self.flood(seed, &self.white);
+
+            let mut len = 0;
+            for index in component.iter() {
+                labels[index] = components.len();
+                len += 1;
+            }
+            components.push(WhiteComponent {
+                len,
+                number: None,
+                num_numbers: 0,
+            });
+            remaining = remaining.and_not(&component);
+        }
+
+        for &(coord, number) in &self.numbers {
+            let component = &mut components[labels[self.coord_to_index(coord)]];
+            // An island with a `?` clue can grow to cover the whole board
+            component.number = Some(number.unwrap_or(self.num_rows * self.num_cols));
+            component.num_numbers += 1;
+        }
+
+        (labels, components)
+    }
+
+    /// Prepares the reachability analysis of unknown cells by white/numbered regions.
+    pub fn reachability(&self) -> Reachability<'_> {
+        let unknown = self.unknown();
+        let stops = self.neighbors(&self.white).and(&unknown);
+        let (labels, components) = self.white_components();
+
+        // The maximum size a white region can be if we still want to be able to join it with a numbered region
+        let max_white_region_len = components
+            .iter()
+            .filter_map(|component| {
+                component
+                    .number
+                    .map(|number| number.saturating_sub(component.len))
+            })
+            .max()
+            .unwrap_or(0);
+
+        // A path of unknown cells ends at the first cell adjacent to a white/numbered region. The
+        // budget of such a cell is the maximum length of a path ending in it that could still be
+        // fused with its adjacent regions.
+        let mut budgets = vec![];
+        for index in stops.iter() {
+            let mut adj_labels = self
+                .neighbor_indices(index)
+                .map(|adj_index| labels[adj_index])
+                .filter(|&label| label != usize::MAX)
+                .collect::<Vec<_>>();
+            adj_labels.sort_unstable();
+            adj_labels.dedup();
+
+            let mut numbered = None;
+            let mut num_numbered = 0;
+            let mut white_len = 0;
+            for &label in &adj_labels {
+                let component = &components[label];
+                if let Some(number) = component.number {
+                    numbered = Some((number, component.len));
+                    num_numbered += component.num_numbers;
+                } else {
+                    white_len += component.len;
+                }
+            }
+
+            let budget = match numbered {
+                // Cannot join two numbered regions
+                _ if num_numbered >= 2 => None,
+                Some((number, len)) => number.checked_sub(len + white_len),
+                None => max_white_region_len.checked_sub(white_len + 1),
+            };
+            if let Some(budget) = budget.filter(|&budget| budget > 0) {
+                budgets.push((index, budget));
+            }
+        }
+        budgets.sort_unstable_by_key(|&(_, budget)| std::cmp::Reverse(budget));
+
+        Reachability {
+            grid: self,
+            free: unknown.and_not(&stops),
+            unknown,
+            budgets,
+        }
+    }
+}
+
+/// Reachability analysis of a [`BitGrid`], created by [`BitGrid::reachability`].
+///
+/// Mirrors [`Grid::is_cell_unreachable`], but instead of searching from every cell it sweeps
+/// backwards from the cells adjacent to white/numbered regions, one path length at a time.
+pub struct Reachability<'a> {
+    grid: &'a BitGrid,
+    unknown: BitSet,
+    /// Unknown cells not adjacent to any white/numbered region, which paths can pass through
+    free: BitSet,
+    /// Cells adjacent to white/numbered regions with a positive budget, by decreasing budget
+    budgets: Vec<(usize, usize)>,
+}
+
+impl Reachability<'_> {
+    /// Returns the unknown cells that some white/numbered region could still reach, when paths may
+    /// not contain any of the `blocked` cells.
+    fn reachable(&self, blocked: &BitSet) -> BitSet {
+        let grid = self.grid;
+        let passable = self.free.and_not(blocked);
+        let mut reachable = BitSet::new(grid.num_rows * grid.stride);
+        let mut budgets = self.budgets.iter().peekable();
+
+        let max_budget = budgets.peek().map_or(0, |&&(_, budget)| budget);
+        for budget in (1..=max_budget).rev() {
+            reachable = reachable.or(&grid.neighbors(&reachable).and(&passable));
+            while let Some(&(index, _)) = budgets.next_if(|&&(_, b)| b == budget) {
+                if !blocked.get(index) {
+                    reachable.set(index);
+                }
+            }
+        }
+
+        reachable
+    }
+
+    /// Returns the unknown cells that cannot be reached by any white/numbered region, in row-major
+    /// order.
+    pub fn unreachable_cells(&self) -> Vec<Coord> {
+        self.unknown
+            .and_not(&self.reachable(&BitSet::new(self.unknown.len())))
+            .iter()
+            .map(|index| self.grid.index_to_coord(index))
+            .collect()
+    }
+
+    /// Check if a cell is unreachable by a white/numbered region, assuming the given cells are
+    /// black.
+    pub fn is_cell_unreachable(
+        &self,
+        coord: Coord,
+        assume_black: impl IntoIterator<Item = Coord>,
+    ) -> bool {
+        let index = self.grid.coord_to_index(coord);
+        if !self.unknown.get(index) {
+            return false;
+        }
+
+        let mut blocked = BitSet::new(self.unknown.len());
+        for coord in assume_black {
+            blocked.set(self.grid.coord_to_index(coord));
+        }
+        // The cell itself is always explored, even if it is assumed to be black
+        blocked.unset(index);
+
+        !self.reachable(&blocked).get(index)
+    }
+}
+
+impl From<&Grid> for BitGrid {
+    fn from(grid: &Grid) -> Self {
+        let mut bit_grid = Self::new_empty(
+            grid.topology.clone(),
+            grid.adjacency.clone(),
+            grid.rules.clone(),
+        );
+        if let Some(board) = grid.topology.as_board() {
+            if board.is_wrapping() {
+                bit_grid.set_wrap();
+            }
+            if board.layout() == Layout::Hex {
+                bit_grid.set_hex();
+            }
+        }
+
+        for row in 0..grid.num_rows {
+            for col in 0..grid.num_cols {
+                let coord = Coord::new(row, col);
+                if grid.is_void(coord) {
+                    bit_grid.board.unset(bit_grid.coord_to_index(coord));
+                }
+            }
+        }
+
+        for (coord, cell) in grid.iter() {
+            let index = bit_grid.coord_to_index(coord);
+            match cell.state {
+                Some(State::Numbered(number)) => {
+                    bit_grid.white.set(index);
+                    bit_grid.numbers.push((coord, number));
+                }
+                Some(State::White) => bit_grid.white.set(index),
+                Some(State::Black) => bit_grid.black.set(index),
+                None => {}
+            }
+        }
+
+        bit_grid
+    }
+}
+
+impl TryFrom<&BitGrid> for Grid {
+    type Error = SolverError;
+
+    fn try_from(bit_grid: &BitGrid) -> Result<Self, SolverError> {
+        let mut grid =
+            Grid::from_topology(bit_grid.topology.clone(), bit_grid.numbers.iter().copied())
+                .with_shared_rules(bit_grid.rules.clone());
+
+        for index in bit_grid.black.iter() {
+            grid.mark_cell(bit_grid.index_to_coord(index), State::Black)?;
+        }
+        for index in bit_grid.white.iter() {
+            let coord = bit_grid.index_to_coord(index);
+            if grid.cell(coord).state.is_none() {
+                grid.mark_cell(coord, State::White)?;
+            }
+        }
+
+        Ok(grid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashSet, VecDeque};
+    use std::str::FromStr;
+
+    use crate::strategy::{
+        avoid_pools::AvoidPools, complete_islands::CompleteIslands, confinement::Confinement,
+        dual_liberties::DualLiberties, hypotheticals::Hypotheticals,
+        single_liberties::SingleLiberties, unreachable_cells::UnreachableCells, Strategy,
+    };
+    use crate::{Coord, Grid, Solver, SolverLogger, State};
+
+    use super::BitGrid;
+
+    fn coords(grid: &Grid) -> impl Iterator<Item = Coord> + '_ {
+        grid.iter().map(|(coord, _)| coord)
+    }
+
+    fn is_state(grid: &Grid, coord: Coord, state: Option<State>) -> bool {
+        !grid.is_void(coord) && grid.cell(coord).state == state
+    }
+
+    fn pool_completions(grid: &Grid) -> Vec<Coord> {
+        let mut completions = HashSet::new();
+        for window in grid.pool_windows() {
+            let unknowns = window
+                .iter()
+                .filter(|&&coord| is_state(grid, coord, None))
+                .collect::<Vec<_>>();
+            let num_black = window
+                .iter()
+                .filter(|&&coord| is_state(grid, coord, Some(State::Black)))
+                .count();
+            if num_black + 1 == window.len() && unknowns.len() == 1 {
+                completions.insert(*unknowns[0]);
+            }
+        }
+        let mut completions = Vec::from_iter(completions);
+        completions.sort();
+        completions
+    }
+
+    fn is_wall_connectable(grid: &Grid) -> bool {
+        let is_passable = |coord| grid.cell(coord).state.is_none_or(State::is_black);
+        let mut black = coords(grid).filter(|&coord| is_state(grid, coord, Some(State::Black)));
+        let Some(start) = black.next() else {
+            return true;
+        };
+
+        let mut explored = HashSet::from([start]);
+        let mut queue = VecDeque::from([start]);
+        while let Some(coord) = queue.pop_front() {
+            for adj_coord in grid.valid_neighbors(coord) {
+                if is_passable(adj_coord) && explored.insert(adj_coord) {
+                    queue.push_back(adj_coord);
+                }
+            }
+        }
+        black.all(|coord| explored.contains(&coord))
+    }
+
+    /// Checks that the bitboard agrees with the grid on every state reached while solving.
+    struct CrossCheckLogger;
+
+    impl SolverLogger for CrossCheckLogger {
+        fn before_apply(&mut self, grid: &Grid) {
+            let bit_grid = BitGrid::from(grid);
+            assert_eq!(
+                Grid::try_from(&bit_grid).unwrap().to_input_string(),
+                grid.to_input_string()
+            );
+
+            assert_eq!(bit_grid.pool_completions(), pool_completions(grid));
+            assert_eq!(bit_grid.is_wall_connectable(), is_wall_connectable(grid));
+
+            let reachability = bit_grid.reachability();
+            assert_eq!(
+                reachability.unreachable_cells(),
+                coords(grid)
+                    .filter(|&coord| grid.is_cell_unreachable(coord, []))
+                    .collect::<Vec<_>>()
+            );
+            for coord in coords(grid) {
+                for adj_coord in grid.valid_unknown_neighbors(coord) {
+                    assert_eq!(
+                        reachability.is_cell_unreachable(coord, [adj_coord]),
+                        grid.is_cell_unreachable(coord, [adj_coord]),
+                    );
+                }
+            }
+        }
+
+        fn strategy_applied(&mut self, _grid: &Grid, _strategy_name: &str) {}
+        fn no_strategy_applies(&mut self, _grid: &Grid) {}
+    }
+
+    fn solve_cross_checked(input: &str) {
+        let basic_strategies = || -> Vec<Box<dyn Strategy>> {
+            vec![
+                Box::new(CompleteIslands),
+                Box::new(SingleLiberties),
+                Box::new(DualLiberties),
+                Box::new(AvoidPools),
+                Box::new(UnreachableCells),
+            ]
+        };
+        let mut strategies = basic_strategies();
+        strategies.push(Box::new(Confinement));
+        strategies.push(Box::new(Hypotheticals::new(basic_strategies())));
+
+        let mut grid = Grid::from_str(input).unwrap();
+        Solver::new(strategies)
+            .solve_with_logger(&mut grid, CrossCheckLogger)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_cross_check_wikipedia() {
+        // https://en.wikipedia.org/wiki/Nurikabe_(puzzle)
+        solve_cross_checked(concat!(
+            "2........2\n",
+            "......2...\n",
+            ".2..7.....\n",
+            "..........\n",
+            "......3.3.\n",
+            "..2....3..\n",
+            "2..4......\n",
+            "..........\n",
+            ".1....2.4.",
+        ));
+    }
+
+    #[test]
+    fn test_cross_check_5x5() {
+        solve_cross_checked("2..1.\n.....\n...3.\n....5\n.....");
+    }
+
+    #[test]
+    fn test_cross_check_void_cells() {
+        solve_cross_checked("-1.4-\n.....\n.1-..\n.....\n-..3-");
+    }
+
+    #[test]
+    fn test_cross_check_wrap() {
+        solve_cross_checked("# wrap: true\n3....\n..3..\n.....\n....2\n.1.1.");
+    }
+
+    #[test]
+    fn test_wrap() {
+        let bit_grid = BitGrid::from(&Grid::from_str("# wrap: true\nB.B\n...\nB..").unwrap());
+        assert!(!bit_grid.has_pool());
+        assert_eq!(bit_grid.pool_completions(), [Coord::new(2, 2)]);
+
+        let bit_grid = BitGrid::from(&Grid::from_str("# wrap: true\nB.B\n...\nB.B").unwrap());
+        assert!(bit_grid.has_pool());
+
+        let bit_grid = BitGrid::from(&Grid::from_str("# wrap: true\nB.W\nW.B").unwrap());
+        assert!(bit_grid.is_wall_connectable());
+    }
+
+    #[test]
+    fn test_cross_check_hex() {
+        solve_cross_checked(
+            "# layout: hex\n. 1 . . 1\n . . . . .\n. . 4 . .\n . . . 2 .\n. 2 . . .",
+        );
+    }
+
+    #[test]
+    fn test_hex() {
+        let bit_grid = BitGrid::from(&Grid::from_str("# layout: hex\n. B .\n B . .").unwrap());
+        assert!(!bit_grid.has_pool());
+        assert_eq!(
+            bit_grid.pool_completions(),
+            [Coord::new(0, 0), Coord::new(1, 1)]
+        );
+
+        let bit_grid = BitGrid::from(&Grid::from_str("# layout: hex\n. B .\n B B .").unwrap());
+        assert!(bit_grid.has_pool());
+
+        // Connected through the neighbor to the top-right of the odd row
+        let bit_grid = BitGrid::from(&Grid::from_str("# layout: hex\nW B\n B W").unwrap());
+        assert!(bit_grid.is_wall_connectable());
+    }
+
+    #[test]
+    fn test_void_cells() {
+        let bit_grid = BitGrid::from(&Grid::from_str("BB-\nB..\n-BB").unwrap());
+        assert!(bit_grid.is_void(Coord::new(0, 2)));
+        assert!(!bit_grid.is_unknown(Coord::new(0, 2)));
+        assert_eq!(bit_grid.pool_completions(), [Coord::new(1, 1)]);
+
+        let bit_grid = BitGrid::from(&Grid::from_str("B-B\nB-B").unwrap());
+        assert!(!bit_grid.is_wall_connectable());
+    }
+
+    #[test]
+    fn test_round_trip_rules() {
+        let input = "# variant: mochikoro\n2B.\n...\n..1";
+        let grid = Grid::try_from(&BitGrid::from(&Grid::from_str(input).unwrap())).unwrap();
+        assert_eq!(grid.rules().name(), "mochikoro");
+        assert_eq!(grid.to_input_string(), input);
+    }
+
+    #[test]
+    fn test_pools() {
+        let bit_grid = BitGrid::from(&Grid::from_str("BB.\nB..\n.BB").unwrap());
+        assert!(!bit_grid.has_pool());
+        assert_eq!(bit_grid.pool_completions(), [Coord::new(1, 1)]);
+
+        let bit_grid = BitGrid::from(&Grid::from_str("B.B\n.BB\n.BB").unwrap());
+        assert!(bit_grid.has_pool());
+    }
+
+    #[test]
+    fn test_wall_connectivity() {
+        let bit_grid = BitGrid::from(&Grid::from_str("B.W\nW.B").unwrap());
+        assert!(bit_grid.is_wall_connectable());
+
+        let bit_grid = BitGrid::from(&Grid::from_str("BWW\nW.B").unwrap());
+        assert!(!bit_grid.is_wall_connectable());
+    }
+}
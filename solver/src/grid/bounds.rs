@@ -0,0 +1,55 @@
+use crate::grid::Region;
+use crate::{Coord, Grid};
+
+/// The smallest rectangle containing a set of cells.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Bounds {
+    pub(crate) min: Coord,
+    pub(crate) max: Coord,
+}
+
+impl Bounds {
+    pub(crate) fn new(coord: Coord) -> Self {
+        Self {
+            min: coord,
+            max: coord,
+        }
+    }
+
+    /// Returns the bounds grown to contain the given cell.
+    pub(crate) fn extend(self, coord: Coord) -> Self {
+        Self {
+            min: Coord::new(self.min.row.min(coord.row), self.min.col.min(coord.col)),
+            max: Coord::new(self.max.row.max(coord.row), self.max.col.max(coord.col)),
+        }
+    }
+
+    pub(crate) fn contains(&self, coord: Coord) -> bool {
+        (self.min.row..=self.max.row).contains(&coord.row)
+            && (self.min.col..=self.max.col).contains(&coord.col)
+    }
+
+    pub(crate) fn area(&self) -> usize {
+        (self.max.row - self.min.row + 1) * (self.max.col - self.min.col + 1)
+    }
+
+    /// Returns `true` if the bounds are one cell wide, in either direction.
+    pub(crate) fn is_thin(&self) -> bool {
+        self.min.row == self.max.row || self.min.col == self.max.col
+    }
+
+    pub(crate) fn coords(&self) -> impl Iterator<Item = Coord> {
+        let Self { min, max } = *self;
+        (min.row..=max.row)
+            .flat_map(move |row| (min.col..=max.col).map(move |col| Coord::new(row, col)))
+    }
+}
+
+impl Grid {
+    /// Returns the bounds of the cells in the region.
+    pub(crate) fn region_bounds(&self, region: &Region) -> Bounds {
+        let mut coords = self.region_coords(region);
+        let first = coords.next().unwrap();
+        coords.fold(Bounds::new(first), |bounds, coord| bounds.extend(coord))
+    }
+}
@@ -0,0 +1,138 @@
+use std::collections::HashSet;
+use std::sync::OnceLock;
+
+use crate::bitgrid::BitGrid;
+use crate::grid::RegionID;
+use crate::{Coord, Grid, SolverError};
+
+/// Results of analyzing a grid, computed on first use and shared by all strategies until the grid
+/// changes.
+#[derive(Clone, Default)]
+pub(crate) struct AnalysisCache {
+    max_white_region_len: OnceLock<usize>,
+    num_black_cells: OnceLock<usize>,
+    possible_owner_counts: OnceLock<Box<[usize]>>,
+    bit_grid: OnceLock<BitGrid>,
+    unreachable_cells: OnceLock<Vec<Coord>>,
+    /// Confinement floods without any assumptions, indexed by region ID
+    confinement_floods: OnceLock<Box<[OnceLock<ConfinementFlood>]>>,
+}
+
+/// The outcome of [`Grid::confinement_flood`].
+#[derive(Clone, Debug)]
+pub(crate) struct ConfinementFlood {
+    pub(crate) result: Result<bool, SolverError>,
+    /// Cells the flood visited before it finished. Assuming any other cell to be visited would not
+    /// have changed the result.
+    pub(crate) visited: HashSet<Coord>,
+}
+
+impl Grid {
+    /// Drops all cached analysis results. Must be called whenever the grid changes.
+    pub(crate) fn invalidate_analysis(&mut self) {
+        self.analysis = AnalysisCache::default();
+    }
+
+    pub(crate) fn max_white_region_len(&self) -> usize {
+        *self
+            .analysis
+            .max_white_region_len
+            .get_or_init(|| self.compute_max_white_region_len())
+    }
+
+    /// Returns the number of cells marked black so far.
+    pub(crate) fn num_black_cells(&self) -> usize {
+        *self
+            .analysis
+            .num_black_cells
+            .get_or_init(|| self.compute_num_black_cells())
+    }
+
+    pub(crate) fn possible_owner_counts(&self) -> &[usize] {
+        self.analysis
+            .possible_owner_counts
+            .get_or_init(|| self.compute_possible_owner_counts())
+    }
+
+    pub(crate) fn bit_grid(&self) -> &BitGrid {
+        self.analysis.bit_grid.get_or_init(|| BitGrid::from(self))
+    }
+
+    /// Returns the unknown cells that cannot be reached by any white/numbered region, in row-major
+    /// order. Always empty if white regions don't need numbers.
+    pub(crate) fn unreachable_cells(&self) -> &[Coord] {
+        self.analysis.unreachable_cells.get_or_init(|| {
+            if self.rules.white_regions_need_numbers() {
+                self.bit_grid().reachability().unreachable_cells()
+            } else {
+                vec![]
+            }
+        })
+    }
+
+    /// Check if a region is confined, i.e. it can't grow to its required size, assuming the given
+    /// unknown cells can't be part of it.
+    pub(crate) fn is_region_confined(
+        &self,
+        region_id: RegionID,
+        assume_visited: impl IntoIterator<Item = Coord>,
+    ) -> Result<bool, SolverError> {
+        let floods = self.analysis.confinement_floods.get_or_init(|| {
+            (0..self.cells.len())
+                .map(|_| OnceLock::new())
+                .collect::<Vec<_>>()
+                .into_boxed_slice()
+        });
+        let flood =
+            floods[region_id.to_index()].get_or_init(|| self.confinement_flood(region_id, []));
+
+        let assume_visited = Vec::from_iter(assume_visited);
+        if assume_visited
+            .iter()
+            .any(|coord| flood.visited.contains(coord))
+        {
+            self.confinement_flood(region_id, assume_visited).result
+        } else {
+            flood.result
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::{Coord, Grid, State};
+
+    #[test]
+    fn test_invalidate_on_change() {
+        let mut grid = Grid::from_str("2..\n...\n...").unwrap();
+        assert_eq!(grid.max_white_region_len(), 1);
+        assert_eq!(grid.unreachable_cells().len(), 6);
+
+        let checkpoint = grid.checkpoint();
+        grid.mark_cell(Coord::new(0, 1), State::White).unwrap();
+        assert_eq!(grid.max_white_region_len(), 0);
+        assert_eq!(grid.unreachable_cells().len(), 7);
+
+        grid.rollback(checkpoint);
+        assert_eq!(grid.max_white_region_len(), 1);
+        assert_eq!(grid.unreachable_cells().len(), 6);
+    }
+
+    #[test]
+    fn test_cached_confinement() {
+        let grid = Grid::from_str("3.....\n......\n..B...\n....2.\n......").unwrap();
+
+        for (region_id, _) in grid.regions_iter() {
+            for (coord, cell) in grid.iter() {
+                if cell.state.is_none() {
+                    assert_eq!(
+                        grid.is_region_confined(region_id, [coord]),
+                        grid.confinement_flood(region_id, [coord]).result
+                    );
+                }
+            }
+        }
+    }
+}
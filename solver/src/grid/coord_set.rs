@@ -0,0 +1,99 @@
+use std::slice;
+
+use crate::Coord;
+
+/// A set of coordinates, stored as a sorted vector.
+///
+/// Lookups are binary searches, but inserting and removing shift the elements after the position,
+/// so they take linear time. Liberty sets are small for most regions, where moving a few
+/// contiguous elements is still cheaper than the allocations and hashing of tree- or hash-based
+/// sets.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct CoordSet(Vec<Coord>);
+
+impl CoordSet {
+    pub(crate) fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Adds a coordinate to the set. Returns whether it was newly inserted.
+    pub(crate) fn insert(&mut self, coord: Coord) -> bool {
+        match self.0.binary_search(&coord) {
+            Ok(_) => false,
+            Err(index) => {
+                self.0.insert(index, coord);
+                true
+            }
+        }
+    }
+
+    /// Removes a coordinate from the set. Returns whether it was present.
+    pub(crate) fn remove(&mut self, coord: &Coord) -> bool {
+        match self.0.binary_search(coord) {
+            Ok(index) => {
+                self.0.remove(index);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// Returns the smallest coordinate in the set.
+    pub(crate) fn first(&self) -> Option<&Coord> {
+        self.0.first()
+    }
+
+    /// Returns the largest coordinate in the set.
+    pub(crate) fn last(&self) -> Option<&Coord> {
+        self.0.last()
+    }
+
+    pub(crate) fn iter(&self) -> slice::Iter<'_, Coord> {
+        self.0.iter()
+    }
+}
+
+impl FromIterator<Coord> for CoordSet {
+    fn from_iter<T: IntoIterator<Item = Coord>>(iter: T) -> Self {
+        let mut coords = Vec::from_iter(iter);
+        coords.sort_unstable();
+        coords.dedup();
+        Self(coords)
+    }
+}
+
+impl<'a> IntoIterator for &'a CoordSet {
+    type Item = &'a Coord;
+    type IntoIter = slice::Iter<'a, Coord>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CoordSet;
+    use crate::Coord;
+
+    #[test]
+    fn test_coord_set() {
+        let mut set = CoordSet::from_iter([Coord::new(1, 0), Coord::new(0, 1), Coord::new(1, 0)]);
+        assert_eq!(set.len(), 2);
+        assert_eq!(set.first(), Some(&Coord::new(0, 1)));
+
+        assert!(set.insert(Coord::new(0, 0)));
+        assert!(!set.insert(Coord::new(1, 0)));
+
+        assert!(set.remove(&Coord::new(1, 0)));
+        assert!(!set.remove(&Coord::new(1, 0)));
+        assert_eq!(
+            set.iter().copied().collect::<Vec<_>>(),
+            [Coord::new(0, 0), Coord::new(0, 1)]
+        );
+    }
+}
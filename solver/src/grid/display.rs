@@ -2,7 +2,7 @@ use std::fmt::{Display, Formatter};
 
 use colored::Colorize;
 
-use crate::{Coord, Grid, State};
+use crate::{Coord, Grid, Layout, State};
 
 impl Display for Grid {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
@@ -31,7 +31,15 @@ pub(crate) struct GridDiff<'a> {
 
 impl<'a> Display for GridDiff<'a> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        // Hex cells are one column wider, so odd rows can be shifted by exactly half a cell
+        let is_hex =
+            (self.grid.topology().as_board()).is_some_and(|board| board.layout() == Layout::Hex);
+        let width = if is_hex { 4 } else { 3 };
+
         for row in 0..self.grid.num_rows {
+            if is_hex && row % 2 == 1 {
+                write!(f, "{:1$}", "", width / 2)?;
+            }
             for col in 0..self.grid.num_cols {
                 let state = self.grid.cell(Coord::new(row, col)).state;
                 let prev_state = self
@@ -41,15 +49,18 @@ impl<'a> Display for GridDiff<'a> {
 
                 let string = match (state, prev_state) {
                     (Some(State::Numbered(number)), _) => {
-                        format!("{:^3}", number.to_string().black())
+                        let number = number.map_or("?".to_string(), |number| number.to_string());
+                        format!("{:^width$}", number.black())
                     }
                     (state, Some(prev_state)) if state != prev_state => {
-                        format!("{:^3}", "*".bright_red())
+                        format!("{:^width$}", "*".bright_red())
                     }
-                    _ => format!("{:3}", ""),
+                    _ => format!("{:width$}", ""),
                 };
 
                 match state {
+                    // Void cells are left blank, without a background
+                    None if self.grid.is_void(Coord::new(row, col)) => write!(f, "{:width$}", "")?,
                     None => write!(f, "{}", string.on_white())?,
                     Some(State::White | State::Numbered(_)) => {
                         write!(f, "{}", string.on_bright_white())?
@@ -1,23 +1,28 @@
 use std::str::FromStr;
 
 use nom::branch::alt;
-use nom::character::complete::{char, digit1, line_ending, not_line_ending};
+use nom::character::complete::{char, digit1, line_ending, not_line_ending, space0};
 use nom::combinator::{map, value};
 use nom::multi::{many0, many1, separated_list1};
-use nom::sequence::delimited;
+use nom::sequence::{delimited, preceded, terminated};
 use nom::IResult;
 
-use crate::{Coord, Grid, State};
+use crate::{BoardOptions, Coord, Grid, Layout, State, Variant};
 
 impl FromStr for Grid {
     type Err = ();
 
     fn from_str(input: &str) -> Result<Self, ()> {
-        let (_, grid) = parse_grid(input).map_err(|_| ())?;
+        let (_, (header, grid)) = parse_grid(input).map_err(|_| ())?;
 
         let num_cols = grid[0].len();
         let num_rows = grid.len();
+        // The first and last rows of a wrapping hex board would be shifted the same way
+        if header.wrap && header.layout == Layout::Hex && num_rows % 2 != 0 {
+            return Err(());
+        }
         let mut givens = vec![];
+        let mut void_cells = vec![];
 
         let mut mark_as_white = vec![];
         let mut mark_as_black = vec![];
@@ -31,21 +36,32 @@ impl FromStr for Grid {
                 let coord = Coord::new(row_idx, col_idx);
 
                 match state {
-                    Some(State::Numbered(number)) => givens.push((coord, number)),
-                    Some(State::White) => mark_as_white.push(coord),
-                    Some(State::Black) => mark_as_black.push(coord),
-                    None => {}
+                    ParsedCell::Void => void_cells.push(coord),
+                    ParsedCell::Cell(Some(State::Numbered(number))) => givens.push((coord, number)),
+                    ParsedCell::Cell(Some(State::White)) => mark_as_white.push(coord),
+                    ParsedCell::Cell(Some(State::Black)) => mark_as_black.push(coord),
+                    ParsedCell::Cell(None) => {}
                 }
             }
         }
 
-        let mut grid = Grid::new(num_rows, num_cols, givens);
+        let options = BoardOptions {
+            void_cells,
+            wrap: header.wrap,
+            layout: header.layout,
+        };
+        let grid = Grid::with_options(num_rows, num_cols, options, givens);
+        if !grid.supports_rules(&header.variant) {
+            return Err(());
+        }
+        let mut grid = grid.with_variant(header.variant);
 
+        // Marked cells can contradict the clues, e.g. across the edges of a wrapping board
         for coord in mark_as_white {
-            grid.mark_cell(coord, State::White).unwrap();
+            grid.mark_cell(coord, State::White).map_err(|_| ())?;
         }
         for coord in mark_as_black {
-            grid.mark_cell(coord, State::Black).unwrap();
+            grid.mark_cell(coord, State::Black).map_err(|_| ())?;
         }
 
         Ok(grid)
@@ -53,16 +69,36 @@ impl FromStr for Grid {
 }
 
 impl Grid {
+    /// Returns the grid in the format read by [`Grid::from_str`]. The variant is only written for
+    /// built-in variants, as custom rule sets can't be parsed back.
     pub fn to_input_string(&self) -> String {
         let mut result = String::new();
+        let variant = Variant::from_str(self.rules.name());
+        if variant.is_ok_and(|variant| variant != Variant::default()) {
+            result.push_str(&format!("# variant: {}\n", self.rules.name()));
+        }
+        let board = self.topology.as_board();
+        if board.is_some_and(|board| board.is_wrapping()) {
+            result.push_str("# wrap: true\n");
+        }
+        let is_hex = board.is_some_and(|board| board.layout() == Layout::Hex);
+        if is_hex {
+            result.push_str("# layout: hex\n");
+        }
 
-        for row in self.cells.chunks(self.num_cols) {
-            for cell in row {
+        for (row_idx, row) in self.cells.chunks(self.num_cols).enumerate() {
+            for (col_idx, cell) in row.iter().enumerate() {
+                // Hex rows are staggered, with every odd row shifted right by half a cell
+                if is_hex && (col_idx > 0 || row_idx % 2 == 1) {
+                    result.push(' ');
+                }
                 match cell.state {
+                    None if self.is_void(Coord::new(row_idx, col_idx)) => result.push('-'),
                     None => result.push('.'),
                     Some(State::White) => result.push('W'),
                     Some(State::Black) => result.push('B'),
-                    Some(State::Numbered(n)) => result.push_str(n.to_string().as_str()),
+                    Some(State::Numbered(Some(n))) => result.push_str(n.to_string().as_str()),
+                    Some(State::Numbered(None)) => result.push('?'),
                 };
             }
             result.push('\n');
@@ -73,26 +109,71 @@ impl Grid {
     }
 }
 
-fn parse_grid(input: &str) -> IResult<&str, Vec<Vec<Option<State>>>> {
-    let (input, _) = parse_header(input)?;
-    separated_list1(line_ending, parse_row)(input)
+/// A cell of the input: either void (i.e. not part of the board) or a cell with an optional state.
+#[derive(Clone, Copy)]
+enum ParsedCell {
+    Void,
+    Cell(Option<State>),
 }
 
-/// Parse the header comments (i.e. lines starting with `#`).
-fn parse_header(input: &str) -> IResult<&str, ()> {
-    let (input, _) = many0(delimited(char('#'), not_line_ending, line_ending))(input)?;
-    Ok((input, ()))
+/// Settings given in the header comments.
+#[derive(Default)]
+struct Header {
+    /// Given by a `# variant: <name>` line
+    variant: Variant,
+    /// Given by a `# wrap: true` line
+    wrap: bool,
+    /// Given by a `# layout: hex` line
+    layout: Layout,
+}
+
+type ParsedGrid = (Header, Vec<Vec<ParsedCell>>);
+
+fn parse_grid(input: &str) -> IResult<&str, ParsedGrid> {
+    let (input, header) = parse_header(input)?;
+    let (input, rows) = separated_list1(line_ending, parse_row)(input)?;
+    Ok((input, (header, rows)))
+}
+
+/// Parse the header comments (i.e. lines starting with `#`). Other comments are ignored.
+fn parse_header(input: &str) -> IResult<&str, Header> {
+    let (rest, comments) = many0(delimited(char('#'), not_line_ending, line_ending))(input)?;
+    let failure =
+        || nom::Err::Failure(nom::error::Error::new(input, nom::error::ErrorKind::Verify));
+
+    let mut header = Header::default();
+    for comment in comments {
+        let comment = comment.trim();
+        if let Some(name) = comment.strip_prefix("variant:") {
+            header.variant = name.trim().parse().map_err(|_| failure())?;
+        } else if let Some(wrap) = comment.strip_prefix("wrap:") {
+            header.wrap = wrap.trim().parse().map_err(|_| failure())?;
+        } else if let Some(layout) = comment.strip_prefix("layout:") {
+            header.layout = match layout.trim() {
+                "square" => Layout::Square,
+                "hex" => Layout::Hex,
+                _ => return Err(failure()),
+            };
+        }
+    }
+    Ok((rest, header))
 }
 
-fn parse_row(input: &str) -> IResult<&str, Vec<Option<State>>> {
-    many1(parse_cell)(input)
+/// Parse a row of cells. Cells may be separated by spaces, which hex boards use to stagger rows.
+fn parse_row(input: &str) -> IResult<&str, Vec<ParsedCell>> {
+    terminated(many1(preceded(space0, parse_cell)), space0)(input)
 }
 
-fn parse_cell(input: &str) -> IResult<&str, Option<State>> {
+/// Parse a single cell. `-` is a void cell; `#` can't be used since it starts header comments.
+fn parse_cell(input: &str) -> IResult<&str, ParsedCell> {
     alt((
-        value(None, char('.')),
-        value(Some(State::White), char('W')),
-        value(Some(State::Black), char('B')),
-        map(digit1, |s: &str| Some(State::Numbered(s.parse().unwrap()))),
+        value(ParsedCell::Void, char('-')),
+        value(ParsedCell::Cell(None), char('.')),
+        value(ParsedCell::Cell(Some(State::White)), char('W')),
+        value(ParsedCell::Cell(Some(State::Black)), char('B')),
+        value(ParsedCell::Cell(Some(State::Numbered(None))), char('?')),
+        map(digit1, |s: &str| {
+            ParsedCell::Cell(Some(State::Numbered(Some(s.parse().unwrap()))))
+        }),
     ))(input)
 }
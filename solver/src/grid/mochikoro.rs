@@ -0,0 +1,124 @@
+use std::collections::VecDeque;
+
+use crate::grid::bounds::Bounds;
+use crate::{Grid, SolverError, State};
+
+impl Grid {
+    /// Returns `true` if the cells within the bounds could all be made part of one rectangular
+    /// white region, i.e. none of them are black or void and they contain at most one number,
+    /// which is at least the area of the bounds.
+    pub(crate) fn is_rectangle_possible(&self, bounds: Bounds) -> bool {
+        if bounds.coords().any(|coord| self.is_void(coord)) {
+            return false;
+        }
+
+        let mut numbers = bounds
+            .coords()
+            .filter_map(|coord| self.cell(coord).state)
+            .filter(|state| !state.is_white());
+
+        match (numbers.next(), numbers.next()) {
+            (None, _) => true,
+            (Some(State::Numbered(number)), None) => {
+                number.is_none_or(|number| bounds.area() <= number)
+            }
+            // A black cell, or a second number
+            _ => false,
+        }
+    }
+
+    /// Returns `true` if all white cells could still be connected, either orthogonally or
+    /// diagonally, through cells that aren't black.
+    pub(crate) fn is_white_diagonally_connectable(&self) -> bool {
+        let Some((start, _)) = self.iter().find(|(_, cell)| {
+            cell.state
+                .is_some_and(|state| state.is_white() || state.is_numbered())
+        }) else {
+            return true;
+        };
+
+        let mut visited = vec![false; self.cells.len()];
+        visited[self.coord_to_index(start)] = true;
+        let mut queue = VecDeque::from([start]);
+
+        while let Some(coord) = queue.pop_front() {
+            for adj_coord in self.valid_surrounding_neighbors(coord) {
+                let index = self.coord_to_index(adj_coord);
+                if !visited[index] && !self.cells[index].state.is_some_and(State::is_black) {
+                    visited[index] = true;
+                    queue.push_back(adj_coord);
+                }
+            }
+        }
+
+        self.iter().all(|(coord, cell)| {
+            visited[self.coord_to_index(coord)]
+                || !cell
+                    .state
+                    .is_some_and(|state| state.is_white() || state.is_numbered())
+        })
+    }
+
+    /// Checks that every white region can still grow into a rectangle.
+    pub(crate) fn detect_rectangle_contradictions(&self) -> Result<(), SolverError> {
+        for region in self.regions() {
+            if !region.state.is_black() && !self.is_rectangle_possible(self.region_bounds(region)) {
+                return Err(SolverError::Contradiction);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::{Grid, SolverError};
+
+    fn mochikoro(input: &str) -> Grid {
+        Grid::from_str(&format!("# variant: mochikoro\n{}", input)).unwrap()
+    }
+
+    #[test]
+    fn test_parse_variant() {
+        assert_eq!(
+            Grid::from_str("# variant: MOCHIKORO\n..")
+                .unwrap()
+                .rules()
+                .name(),
+            "mochikoro"
+        );
+        assert!(Grid::from_str("# variant: sudoku\n..").is_err());
+        assert_eq!(
+            mochikoro("1.").to_input_string(),
+            "# variant: mochikoro\n1."
+        );
+    }
+
+    #[test]
+    fn test_rectangle_contradictions() {
+        assert_eq!(
+            mochikoro("W.\n.W").detect_rectangle_contradictions(),
+            Ok(())
+        );
+        // L-shaped region around a black cell
+        assert_eq!(
+            mochikoro("WW\nWB").detect_rectangle_contradictions(),
+            Err(SolverError::Contradiction)
+        );
+        // Numbered region whose bounds are larger than its number
+        assert_eq!(
+            mochikoro("3W\n.W").detect_rectangle_contradictions(),
+            Err(SolverError::Contradiction)
+        );
+    }
+
+    #[test]
+    fn test_diagonal_connectivity() {
+        assert!(mochikoro("WB.\nBW.\n...").is_white_diagonally_connectable());
+        assert!(!mochikoro("WB.\nBB.\n..W").is_white_diagonally_connectable());
+        assert!(!mochikoro("WBBB\nBBBW").is_white_diagonally_connectable());
+    }
+}
@@ -1,9 +1,31 @@
+use std::iter;
+use std::sync::Arc;
+
 use crate::SolverError;
+use cache::AnalysisCache;
+use coord_set::CoordSet;
+use topology::Adjacency;
+use trail::{Trail, TrailEntry};
+use zobrist::zobrist_key;
 
 mod analysis;
+pub mod bitgrid;
+pub(crate) mod bounds;
+mod cache;
+mod coord_set;
 #[cfg(feature = "display")]
 pub mod display;
 pub mod from_str;
+mod mochikoro;
+mod nuribou;
+mod rules;
+mod topology;
+mod trail;
+mod zobrist;
+
+pub use rules::{RuleSet, Variant};
+pub use topology::{Board, BoardOptions, Layout, Topology};
+pub use trail::Checkpoint;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Ord, PartialOrd)]
 pub struct Coord {
@@ -19,7 +41,9 @@ impl Coord {
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
 pub(crate) enum State {
-    Numbered(usize),
+    /// A clue cell, with the size of its island, or `None` for a `?` clue whose island can have
+    /// any size.
+    Numbered(Option<usize>),
     White,
     Black,
 }
@@ -43,22 +67,19 @@ impl State {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 pub(crate) struct Cell {
-    // TODO: state.is_some() <=> region.is_some(), so we could use a single Option<StateOrRegion>?
     pub(crate) state: Option<State>,
-    pub(crate) region: Option<RegionID>,
-}
-
-impl Default for Cell {
-    fn default() -> Self {
-        Self {
-            state: None,
-            region: None,
-        }
-    }
+    /// Index of the parent cell in the union-find forest of regions. A marked cell whose parent is
+    /// itself is the root of its region. Meaningless for unknown cells.
+    parent: usize,
+    /// Index of the next cell in the linked list of the cells of its region. Meaningless for the
+    /// last cell of a region and for unknown cells.
+    next: usize,
 }
 
+/// Identifies a region by the index of its root cell. Region IDs are only stable until the region
+/// is fused with another region.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub(crate) struct RegionID(usize);
 
@@ -71,16 +92,32 @@ impl RegionID {
 #[derive(Clone, Debug)]
 pub(crate) struct Region {
     pub(crate) state: State,
-    /// Coordinates of cells in the region
-    pub(crate) coords: Vec<Coord>,
+    /// Index of the first cell of the region. The cells are linked through [`Cell::next`], so
+    /// fusing regions doesn't need to copy them, see [`Grid::region_coords`].
+    first: usize,
+    /// Index of the last cell of the region
+    last: usize,
+    /// Number of cells in the region
+    len: usize,
     /// Coordinates of unknown cells neighboring the region
-    pub(crate) unknowns: Vec<Coord>,
+    pub(crate) unknowns: CoordSet,
 }
 
 impl Region {
+    /// Creates a region containing only the cell with the given index.
+    fn new(state: State, index: usize, unknowns: CoordSet) -> Self {
+        Self {
+            state,
+            first: index,
+            last: index,
+            len: 1,
+            unknowns,
+        }
+    }
+
     /// Returns the number of cells in the region.
     pub(crate) fn len(&self) -> usize {
-        self.coords.len()
+        self.len
     }
 
     /// Returns the number of unknown cells neighboring the region.
@@ -99,9 +136,20 @@ pub struct Grid {
     pub(crate) num_rows: usize,
     pub(crate) num_cols: usize,
     cells: Box<[Cell]>,
-    regions: Vec<Option<Region>>,
-    available_region_ids: Vec<RegionID>,
-    pub(crate) total_black_cells: usize,
+    /// Regions, indexed by the index of their root cell
+    regions: Box<[Option<Region>]>,
+    /// Number of black cells in the solution, or `None` if it is unknown because of `?` clues
+    pub(crate) total_black_cells: Option<usize>,
+    trail: Trail,
+    /// Cells in the order they were marked
+    changes: Vec<Coord>,
+    analysis: AnalysisCache,
+    /// Zobrist hash of the cell states
+    hash: u64,
+    rules: Arc<dyn RuleSet>,
+    topology: Arc<dyn Topology>,
+    /// Neighbors and pools of the cells, as given by the topology
+    adjacency: Arc<Adjacency>,
 }
 
 impl Grid {
@@ -110,43 +158,188 @@ impl Grid {
         num_cols: usize,
         givens: impl IntoIterator<Item = (Coord, usize)>,
     ) -> Self {
-        let mut grid = Self::new_empty(num_rows, num_cols);
+        Self::with_clues(
+            num_rows,
+            num_cols,
+            givens
+                .into_iter()
+                .map(|(coord, given)| (coord, Some(given))),
+        )
+    }
+
+    /// Creates a grid with the given clues, where `None` is a `?` clue: a numbered cell whose
+    /// island can have any size.
+    pub fn with_clues(
+        num_rows: usize,
+        num_cols: usize,
+        givens: impl IntoIterator<Item = (Coord, Option<usize>)>,
+    ) -> Self {
+        Self::with_void_cells(num_rows, num_cols, [], givens)
+    }
+
+    /// Creates a grid with holes or an irregular outline: the void cells are not part of the
+    /// board, so they are neither black nor white.
+    pub fn with_void_cells(
+        num_rows: usize,
+        num_cols: usize,
+        void_cells: impl IntoIterator<Item = Coord>,
+        givens: impl IntoIterator<Item = (Coord, Option<usize>)>,
+    ) -> Self {
+        let options = BoardOptions {
+            void_cells: void_cells.into_iter().collect(),
+            ..Default::default()
+        };
+        Self::with_options(num_rows, num_cols, options, givens)
+    }
+
+    /// Creates a grid with the given board shape and clues.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a wrapping hex board has an odd number of rows, as its first and last rows would
+    /// be shifted the same way, or if a void cell lies outside the board.
+    pub fn with_options(
+        num_rows: usize,
+        num_cols: usize,
+        options: BoardOptions,
+        givens: impl IntoIterator<Item = (Coord, Option<usize>)>,
+    ) -> Self {
+        Self::with_topology(Board::new(num_rows, num_cols, options), givens)
+    }
+
+    /// Creates a grid on a board with the given topology, e.g. one with its own notion of
+    /// neighbors and pools.
+    pub fn with_topology(
+        topology: impl Topology + 'static,
+        givens: impl IntoIterator<Item = (Coord, Option<usize>)>,
+    ) -> Self {
+        Self::from_topology(Arc::new(topology), givens)
+    }
+
+    fn from_topology(
+        topology: Arc<dyn Topology>,
+        givens: impl IntoIterator<Item = (Coord, Option<usize>)>,
+    ) -> Self {
+        let mut grid = Self::new_empty(topology);
 
-        let mut total_white_cells = 0;
+        let mut total_white_cells = Some(0);
 
         for (coord, given) in givens {
             let state = State::Numbered(given);
-            let region_id = grid.insert_region(Region {
-                state,
-                coords: vec![coord],
-                // Note: All neighbors are unknown at this point, otherwise the input would be invalid.
-                unknowns: grid.valid_neighbors(coord).collect(),
-            });
-            *grid.cell_mut(coord) = Cell {
+            let index = grid.coord_to_index(coord);
+            // Note: All neighbors are unknown at this point, otherwise the input would be invalid.
+            let unknowns = grid.valid_neighbors(coord).collect();
+            grid.regions[index] = Some(Region::new(state, index, unknowns));
+            grid.cells[index] = Cell {
                 state: Some(state),
-                region: Some(region_id),
+                parent: index,
+                next: index,
             };
+            grid.hash ^= zobrist_key(index, state);
 
-            total_white_cells += given;
+            total_white_cells = total_white_cells
+                .zip(given)
+                .map(|(total, given)| total + given);
         }
 
-        grid.total_black_cells = num_cols * num_rows - total_white_cells;
+        let num_cells = grid.num_cells();
+        grid.total_black_cells = total_white_cells.map(|total| num_cells - total);
 
         grid
     }
 
-    fn new_empty(num_rows: usize, num_cols: usize) -> Self {
+    fn new_empty(topology: Arc<dyn Topology>) -> Self {
+        let (num_rows, num_cols) = (topology.num_rows(), topology.num_cols());
         Self {
             num_rows,
             num_cols,
             cells: vec![Default::default(); num_cols * num_rows].into_boxed_slice(),
-            regions: vec![],
-            available_region_ids: vec![],
-            total_black_cells: 0,
+            regions: vec![None; num_cols * num_rows].into_boxed_slice(),
+            total_black_cells: None,
+            trail: Trail::default(),
+            changes: vec![],
+            analysis: AnalysisCache::default(),
+            hash: 0,
+            rules: Arc::new(Variant::default()),
+            adjacency: Arc::new(Adjacency::new(topology.as_ref())),
+            topology,
+        }
+    }
+
+    /// Sets the rules the grid is solved with to one of the built-in variants.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the variant is not supported on the board, see [`Grid::supports_rules`].
+    pub fn with_variant(self, variant: Variant) -> Self {
+        self.with_rules(variant)
+    }
+
+    /// Sets the rules the grid is solved with.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the rules are not supported on the board, see [`Grid::supports_rules`].
+    pub fn with_rules(self, rules: impl RuleSet + 'static) -> Self {
+        self.with_shared_rules(Arc::new(rules))
+    }
+
+    /// Returns `true` if the grid can be solved with the given rules. Rectangular islands and
+    /// strip walls are checked on the bounding boxes of regions, which only exist for square cells
+    /// and don't exist across the edges of a wrapping board.
+    pub fn supports_rules(&self, rules: &dyn RuleSet) -> bool {
+        let needs_bounding_boxes = rules.has_rectangular_islands() || rules.has_strip_walls();
+        !needs_bounding_boxes
+            || self
+                .topology
+                .as_board()
+                .is_none_or(|board| !board.is_wrapping() && board.layout() == Layout::Square)
+    }
+
+    fn with_shared_rules(mut self, rules: Arc<dyn RuleSet>) -> Self {
+        assert!(
+            self.supports_rules(rules.as_ref()),
+            "the {} rules are not supported on this board",
+            rules.name()
+        );
+        self.invalidate_analysis();
+        if !rules.white_regions_need_numbers() {
+            // White regions without numbers can have any size
+            self.total_black_cells = None;
         }
+        self.rules = rules;
+        self
+    }
+
+    /// Creates a grid with the given clues on a board of the same shape, with the same rules.
+    pub(crate) fn with_same_board(
+        &self,
+        givens: impl IntoIterator<Item = (Coord, Option<usize>)>,
+    ) -> Self {
+        Self::from_topology(Arc::clone(&self.topology), givens)
+            .with_shared_rules(Arc::clone(&self.rules))
+    }
+
+    pub fn rules(&self) -> &dyn RuleSet {
+        self.rules.as_ref()
+    }
+
+    /// Returns the topology of the board, e.g. to create a grid of the same shape.
+    pub fn topology(&self) -> &Arc<dyn Topology> {
+        &self.topology
+    }
+
+    /// Returns `true` if the cell lies outside the board.
+    pub fn is_void(&self, coord: Coord) -> bool {
+        self.adjacency.void[self.coord_to_index(coord)]
+    }
+
+    /// Returns the number of cells on the board, i.e. excluding void cells.
+    pub fn num_cells(&self) -> usize {
+        self.adjacency.num_cells
     }
 
-    fn coord_to_index(&self, coord: Coord) -> usize {
+    pub(crate) fn coord_to_index(&self, coord: Coord) -> usize {
         coord.row * self.num_cols + coord.col
     }
 
@@ -154,49 +347,71 @@ impl Grid {
         Coord::new(index / self.num_cols, index % self.num_cols)
     }
 
-    pub(crate) fn valid_neighbors(&self, coord: Coord) -> impl Iterator<Item = Coord> {
-        let num_cols = self.num_cols as isize;
-        let num_rows = self.num_rows as isize;
-
-        [
-            (coord.row as isize - 1, coord.col as isize),
-            (coord.row as isize + 1, coord.col as isize),
-            (coord.row as isize, coord.col as isize - 1),
-            (coord.row as isize, coord.col as isize + 1),
-        ]
-        .into_iter()
-        .filter_map(move |(row, col)| {
-            if row >= 0 && row < num_rows && col >= 0 && col < num_cols {
-                Some(Coord::new(row as usize, col as usize))
-            } else {
-                None
-            }
-        })
+    pub(crate) fn valid_neighbors(&self, coord: Coord) -> impl Iterator<Item = Coord> + '_ {
+        self.adjacency.neighbors[self.coord_to_index(coord)]
+            .iter()
+            .copied()
+    }
+
+    /// Returns the cells surrounding the given cell, including ones that only share a corner.
+    pub(crate) fn valid_surrounding_neighbors(
+        &self,
+        coord: Coord,
+    ) -> impl Iterator<Item = Coord> + '_ {
+        self.adjacency.surrounding[self.coord_to_index(coord)]
+            .iter()
+            .copied()
+    }
+
+    /// Returns the groups of cells that may not all be black (2x2 squares, or the three cells
+    /// around a vertex on hex boards).
+    pub(crate) fn pool_windows(&self) -> impl Iterator<Item = &[Coord]> + '_ {
+        self.adjacency.pools.iter().map(|pool| &pool[..])
     }
 
     pub(crate) fn valid_unknown_neighbors(&self, coord: Coord) -> impl Iterator<Item = Coord> + '_ {
         self.valid_neighbors(coord)
-            .filter(move |&coord| self.cell(coord).state == None)
+            .filter(move |&coord| self.cell(coord).state.is_none())
     }
 
     pub(crate) fn cell(&self, coord: Coord) -> &Cell {
         &self.cells[self.coord_to_index(coord)]
     }
 
-    pub(crate) fn cell_mut(&mut self, coord: Coord) -> &mut Cell {
-        &mut self.cells[self.coord_to_index(coord)]
-    }
-
     pub(crate) fn cells(&self) -> impl Iterator<Item = &Cell> {
         self.cells.iter()
     }
 
+    /// Returns the clues of the grid in row-major order, where `None` is a `?` clue.
+    pub fn clues(&self) -> Vec<(Coord, Option<usize>)> {
+        self.iter()
+            .filter_map(|(coord, cell)| match cell.state {
+                Some(State::Numbered(number)) => Some((coord, number)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Returns the cells on the board, skipping void cells.
     pub(crate) fn iter(&self) -> impl Iterator<Item = (Coord, &Cell)> {
         self.cells()
             .enumerate()
+            .filter(|&(index, _)| !self.adjacency.void[index])
             .map(move |(index, cell)| (self.index_to_coord(index), cell))
     }
 
+    /// Returns the ID of the region containing the given cell, or `None` if the cell is unknown.
+    pub(crate) fn region_id(&self, coord: Coord) -> Option<RegionID> {
+        self.cell(coord).state?;
+
+        // No path compression, so that fusing regions stays cheap to undo
+        let mut index = self.coord_to_index(coord);
+        while self.cells[index].parent != index {
+            index = self.cells[index].parent;
+        }
+        Some(RegionID(index))
+    }
+
     pub(crate) fn region(&self, region_id: RegionID) -> Option<&Region> {
         self.regions[region_id.to_index()].as_ref()
     }
@@ -216,53 +431,84 @@ impl Grid {
             .filter_map(|(index, region)| region.as_ref().map(|region| (RegionID(index), region)))
     }
 
-    fn insert_region(&mut self, region: Region) -> RegionID {
-        if let Some(region_id) = self.available_region_ids.pop() {
-            self.regions[region_id.to_index()] = Some(region);
-            region_id
-        } else {
-            let region_id = RegionID(self.regions.len());
-            self.regions.push(Some(region));
-            region_id
-        }
+    /// Returns the coordinates of the cells in the region, in the order they joined it.
+    pub(crate) fn region_coords<'a>(&'a self, region: &Region) -> impl Iterator<Item = Coord> + 'a {
+        iter::successors(Some(region.first), |&index| Some(self.cells[index].next))
+            .take(region.len)
+            .map(|index| self.index_to_coord(index))
+    }
+
+    /// Returns the regions containing or adjacent to any of the given cells.
+    pub(crate) fn regions_near(&self, coords: &[Coord]) -> impl Iterator<Item = &Region> {
+        let mut region_ids = coords
+            .iter()
+            .flat_map(|&coord| {
+                self.region_id(coord).into_iter().chain(
+                    self.valid_neighbors(coord)
+                        .filter_map(|adj_coord| self.region_id(adj_coord)),
+                )
+            })
+            .collect::<Vec<_>>();
+        region_ids.sort_unstable_by_key(|region_id| region_id.to_index());
+        region_ids.dedup();
+
+        region_ids
+            .into_iter()
+            .map(|region_id| self.region(region_id).unwrap())
+    }
+
+    /// Returns the number of cells marked so far. Can be passed to [`Grid::changes_since`] later.
+    pub(crate) fn num_changes(&self) -> usize {
+        self.changes.len()
     }
 
-    fn remove_region(&mut self, region_id: RegionID) -> Option<Region> {
-        self.available_region_ids.push(region_id);
-        self.regions[region_id.to_index()].take()
+    /// Returns the cells marked since the grid had the given number of changes.
+    pub(crate) fn changes_since(&self, num_changes: usize) -> &[Coord] {
+        &self.changes[num_changes..]
     }
 
     pub(crate) fn mark_cell(&mut self, coord: Coord, state: State) -> Result<(), SolverError> {
-        if self.cell(coord).state.is_some() {
-            // If the cell is already marked, we can't mark it again
+        if self.cell(coord).state.is_some() || self.is_void(coord) {
+            // If the cell is already marked or not on the board, we can't mark it
             return Err(SolverError::Contradiction);
         }
 
-        // Create new region containing only the given cell
-        let mut region_id = self.insert_region(Region {
-            state,
-            coords: vec![coord],
-            unknowns: self.valid_unknown_neighbors(coord).collect(),
-        });
-
-        // Mark the given cell, and link it to the new region
-        self.cell_mut(coord).state = Some(state);
-        self.cell_mut(coord).region = Some(region_id);
+        self.invalidate_analysis();
+
+        // Mark the given cell, and create a new region containing only the given cell
+        let index = self.coord_to_index(coord);
+        let unknowns = self.valid_unknown_neighbors(coord).collect();
+        self.regions[index] = Some(Region::new(state, index, unknowns));
+        self.cells[index] = Cell {
+            state: Some(state),
+            parent: index,
+            next: index,
+        };
+        self.trail.record(TrailEntry::CellMarked(coord));
+        self.changes.push(coord);
+        self.hash ^= zobrist_key(index, state);
+        let mut region_id = RegionID(index);
 
         // Update adjacent regions
-        for adjacent_coord in self.valid_neighbors(coord) {
-            if let Some(adjacent_region_id) = self.cell(adjacent_coord).region {
+        let adjacency = Arc::clone(&self.adjacency);
+        for &adjacent_coord in adjacency.neighbors[index].iter() {
+            if let Some(adjacent_region_id) = self.region_id(adjacent_coord) {
                 let adjacent_region = self.region_mut(adjacent_region_id).unwrap();
 
                 // Remove cell from the unknowns of all adjacent regions
-                // TODO: Performance can probably be improved slightly by using .swap_remove()
-                adjacent_region.unknowns.retain(|&unknown| unknown != coord);
+                if adjacent_region.unknowns.remove(&coord) {
+                    self.trail.record(TrailEntry::UnknownRemoved {
+                        region_id: adjacent_region_id,
+                        coord,
+                    });
+                }
+                let adjacent_region = self.region(adjacent_region_id).unwrap();
 
                 // Add cell to adjacent regions with equivalent state, potentially fusing some regions
                 // TODO: Make sure that this equivalence check is correct
                 let is_adjacent_state_equivalent = match adjacent_region.state {
-                    State::White | State::Numbered(_) => state == State::White,
-                    State::Black => state == State::Black,
+                    State::White | State::Numbered(_) => state.is_white(),
+                    State::Black => state.is_black(),
                 };
                 if is_adjacent_state_equivalent {
                     region_id = self.fuse_regions(adjacent_region_id, region_id)?;
@@ -286,53 +532,80 @@ impl Grid {
         let region_1 = self.region(region_id_1).unwrap();
         let region_2 = self.region(region_id_2).unwrap();
 
-        match (region_1.state, region_2.state) {
+        let state = match (region_1.state, region_2.state) {
             (State::Numbered(_), State::Numbered(_)) => {
                 // If both regions are numbered, we can't fuse them
                 return Err(SolverError::Contradiction);
             }
-            (_, State::Numbered(_)) => {
-                // Swap the regions so that region_1 is the numbered region
-                return self.fuse_regions(region_id_2, region_id_1);
-            }
-            (State::Numbered(number), State::White) => {
-                if region_1.len() + region_2.len() > number {
+            (State::Numbered(number), State::White) | (State::White, State::Numbered(number)) => {
+                if number.is_some_and(|number| region_1.len() + region_2.len() > number) {
                     // If the combined regions have more cells than the number, we can't fuse them
                     return Err(SolverError::Contradiction);
                 }
 
                 // => Fuse the regions
+                State::Numbered(number)
             }
             (State::White, State::White) | (State::Black, State::Black) => {
                 // => Fuse the regions
+                region_1.state
             }
             _ => {
                 // If the regions have incompatible states, we can't fuse them
                 return Err(SolverError::Contradiction);
             }
-        }
+        };
 
-        let region_2 = self.remove_region(region_id_2).unwrap();
-        let region_1 = self.region_mut(region_id_1).unwrap();
-
-        // Add new unknowns from region_2 to region_1
-        for coord in region_2.unknowns {
-            if !region_1.unknowns.contains(&coord) {
-                region_1.unknowns.push(coord);
+        // Union by size: the smaller region is absorbed into the larger one
+        let (region_id, absorbed_id) = if region_1.len() >= region_2.len() {
+            (region_id_1, region_id_2)
+        } else {
+            (region_id_2, region_id_1)
+        };
+
+        let absorbed = self.regions[absorbed_id.to_index()].take().unwrap();
+        let region = self.regions[region_id.to_index()].as_mut().unwrap();
+
+        let prev_state = region.state;
+        let (prev_last, prev_len) = (region.last, region.len);
+        region.state = state;
+        // Append the cells of the absorbed region to the linked list
+        region.last = absorbed.last;
+        region.len += absorbed.len;
+        self.cells[prev_last].next = absorbed.first;
+
+        // Add new unknowns from the absorbed region
+        let mut added_unknowns = vec![];
+        for &coord in &absorbed.unknowns {
+            if region.unknowns.insert(coord) {
+                added_unknowns.push(coord);
             }
         }
 
-        // Add cells from region_2 to region_1
-        region_1.coords.extend(&region_2.coords);
-        for coord in region_2.coords {
-            self.cell_mut(coord).region = Some(region_id_1);
+        self.cells[absorbed_id.to_index()].parent = region_id.to_index();
+
+        if self.trail.is_recording() {
+            self.trail.record(TrailEntry::RegionsFused {
+                region_id,
+                absorbed_id,
+                absorbed,
+                prev_state,
+                prev_last,
+                prev_len,
+                added_unknowns,
+            });
         }
 
-        Ok(region_id_1)
+        Ok(region_id)
+    }
+
+    /// Returns the maximum number of cells an island with the given clue can have.
+    pub(crate) fn max_island_len(&self, number: Option<usize>) -> usize {
+        number.unwrap_or(self.num_cells())
     }
 
     pub(crate) fn is_complete(&self) -> bool {
-        let total_cells = self.num_cols * self.num_rows;
+        let total_cells = self.num_cells();
         let marked_cells = self.regions().map(|region| region.len()).sum::<usize>();
         total_cells == marked_cells
     }
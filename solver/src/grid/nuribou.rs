@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+
+use crate::grid::bounds::Bounds;
+use crate::grid::{Region, RegionID};
+use crate::{Grid, SolverError};
+
+impl Grid {
+    /// Returns `true` if a black region with the given bounds and number of cells is a straight
+    /// strip, one cell wide.
+    pub(crate) fn is_strip(bounds: Bounds, len: usize) -> bool {
+        bounds.is_thin() && bounds.area() == len
+    }
+
+    /// Returns `true` if a black strip can't get any longer, because marking any of its unknown
+    /// neighbors black would bend it.
+    pub(crate) fn is_strip_finished(&self, region: &Region) -> bool {
+        let bounds = self.region_bounds(region);
+        !region
+            .unknowns
+            .iter()
+            .any(|&coord| Self::is_strip(bounds.extend(coord), region.len() + 1))
+    }
+
+    /// Checks the rules that only apply to grids with strip walls: every black region must be a
+    /// straight strip, and finished strips of equal length may not touch diagonally.
+    ///
+    /// Strips can't touch orthogonally, since adjacent black cells are fused into one region.
+    /// Touching end to end makes a single longer strip, while touching end to side makes a bent
+    /// region, which is a contradiction. The diagonal rule goes beyond that, following published
+    /// Nuribou puzzles.
+    pub(crate) fn detect_strip_contradictions(&self) -> Result<(), SolverError> {
+        let mut finished_lens = HashMap::new();
+
+        for (region_id, region) in self.regions_iter() {
+            if !region.state.is_black() {
+                continue;
+            }
+            if !Self::is_strip(self.region_bounds(region), region.len()) {
+                return Err(SolverError::Contradiction);
+            }
+            if self.is_strip_finished(region) {
+                finished_lens.insert(region_id, region.len());
+            }
+        }
+
+        for (&region_id, &len) in &finished_lens {
+            let region = self.region(region_id).unwrap();
+            let touches_equal_strip = self.region_coords(region).any(|coord| {
+                self.valid_surrounding_neighbors(coord)
+                    .filter_map(|adj_coord| self.region_id(adj_coord))
+                    .any(|adj_region_id: RegionID| {
+                        adj_region_id != region_id
+                            && finished_lens.get(&adj_region_id) == Some(&len)
+                    })
+            });
+            if touches_equal_strip {
+                return Err(SolverError::Contradiction);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::{Grid, SolverError};
+
+    fn nuribou(input: &str) -> Grid {
+        Grid::from_str(&format!("# variant: nuribou\n{}", input)).unwrap()
+    }
+
+    #[test]
+    fn test_strip_contradictions() {
+        assert_eq!(
+            nuribou("BB.\n..B\n..B").detect_strip_contradictions(),
+            Ok(())
+        );
+        // Bent strip, i.e. two strips touching end to side
+        assert_eq!(
+            nuribou("BB\nB.").detect_strip_contradictions(),
+            Err(SolverError::Contradiction)
+        );
+        // Two strips touching end to end form a single straight strip
+        assert_eq!(nuribou("BBBB\n....").detect_strip_contradictions(), Ok(()));
+        // Finished strips of equal length touching diagonally
+        assert_eq!(
+            nuribou("BBW\nWWB\n..B").detect_strip_contradictions(),
+            Err(SolverError::Contradiction)
+        );
+        // Finished strips of different lengths may touch diagonally
+        assert_eq!(
+            nuribou("BBW\nWWB\nWWB\nWWB\nWWW").detect_strip_contradictions(),
+            Ok(())
+        );
+        // Same, but the second strip could still grow
+        assert_eq!(
+            nuribou("BBW\nWWB\n..B\n...").detect_strip_contradictions(),
+            Ok(())
+        );
+    }
+}
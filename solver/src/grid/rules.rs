@@ -0,0 +1,206 @@
+use std::fmt::{Debug, Display, Formatter};
+use std::str::FromStr;
+
+use super::zobrist::hash_words;
+use crate::{Grid, SolverError};
+
+/// The rules of a Nurikabe-family puzzle. The default methods describe the rules of Nurikabe, so
+/// a variant only needs to override the rules it changes. In every rule set, an island contains at
+/// most one number; regions with different numbers are never fused. Island sizes are not part of
+/// the rule set either: a numbered island always has as many cells as its number, so rules where a
+/// number means something else can't be expressed by overriding these methods.
+///
+/// Strategies consult the rule set of the grid they're applied to and skip deductions that don't
+/// hold under it, so variants can be solved without a solver of their own.
+pub trait RuleSet: Debug + Send + Sync {
+    /// The name of the rule set. Built-in variants are selected by it in `# variant: <name>`
+    /// puzzle headers; custom rule sets can't be, so grids using them must be given their rules
+    /// again after parsing.
+    fn name(&self) -> &str;
+
+    /// Returns `true` if every white region must (eventually) contain a number. Otherwise, white
+    /// regions without a number may have any size.
+    fn white_regions_need_numbers(&self) -> bool {
+        true
+    }
+
+    /// Returns `true` if all black cells must form a single region.
+    fn is_wall_connected(&self) -> bool {
+        true
+    }
+
+    /// Returns `true` if 2x2 squares of black cells are allowed.
+    fn allows_pools(&self) -> bool {
+        false
+    }
+
+    /// Returns `true` if every white region must be a rectangle.
+    fn has_rectangular_islands(&self) -> bool {
+        false
+    }
+
+    /// Returns `true` if all white cells must be connected, orthogonally or diagonally.
+    fn is_white_diagonally_connected(&self) -> bool {
+        false
+    }
+
+    /// Returns `true` if every black region must be a straight strip, one cell wide.
+    fn has_strip_walls(&self) -> bool {
+        false
+    }
+
+    /// Returns a key that tells the rule set apart from others in the
+    /// [Zobrist hash](Grid::zobrist_hash) of a grid, so positions under different rules don't
+    /// share transposition table entries. Defaults to a hash of the name: rule sets that share a
+    /// name but behave differently, e.g. parameterized ones, must override it.
+    fn key(&self) -> u64 {
+        hash_words(self.name().bytes().map(u64::from))
+    }
+
+    /// Checks rules that can't be expressed by the methods above. Called after the built-in
+    /// checks when the solver looks for contradictions.
+    fn detect_contradictions(&self, _grid: &Grid) -> Result<(), SolverError> {
+        Ok(())
+    }
+}
+
+/// The built-in rule sets. Selected in puzzle files with a `# variant: <name>` header.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Variant {
+    /// Every island contains exactly one number, and all black cells are connected.
+    #[default]
+    Nurikabe,
+    /// White regions are rectangles that may or may not contain a number, and all white cells are
+    /// connected diagonally. Black cells don't need to be connected.
+    Mochikoro,
+    /// Every island contains exactly one number, and black cells form straight strips one cell
+    /// wide. Strips may not touch orthogonally, end to end or otherwise: orthogonally adjacent
+    /// black cells belong to the same strip, so strips that touch form a single strip, which must
+    /// be straight. As in published Nuribou puzzles, strips of equal length also may not touch
+    /// diagonally.
+    Nuribou,
+}
+
+impl Variant {
+    pub const ALL: [Variant; 3] = [Variant::Nurikabe, Variant::Mochikoro, Variant::Nuribou];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Variant::Nurikabe => "nurikabe",
+            Variant::Mochikoro => "mochikoro",
+            Variant::Nuribou => "nuribou",
+        }
+    }
+}
+
+impl RuleSet for Variant {
+    fn name(&self) -> &str {
+        Variant::name(*self)
+    }
+
+    fn white_regions_need_numbers(&self) -> bool {
+        matches!(self, Variant::Nurikabe | Variant::Nuribou)
+    }
+
+    fn is_wall_connected(&self) -> bool {
+        *self == Variant::Nurikabe
+    }
+
+    fn has_rectangular_islands(&self) -> bool {
+        *self == Variant::Mochikoro
+    }
+
+    fn is_white_diagonally_connected(&self) -> bool {
+        *self == Variant::Mochikoro
+    }
+
+    fn has_strip_walls(&self) -> bool {
+        *self == Variant::Nuribou
+    }
+}
+
+impl Display for Variant {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(Variant::name(*self))
+    }
+}
+
+impl FromStr for Variant {
+    type Err = ();
+
+    fn from_str(name: &str) -> Result<Self, ()> {
+        Self::ALL
+            .into_iter()
+            .find(|variant| variant.name().eq_ignore_ascii_case(name))
+            .ok_or(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::RuleSet;
+    use crate::config::SolverConfig;
+    use crate::{Grid, Solver, SolverError};
+
+    #[derive(Debug)]
+    struct PoolsAllowed;
+
+    impl RuleSet for PoolsAllowed {
+        fn name(&self) -> &str {
+            "pools-allowed"
+        }
+
+        fn allows_pools(&self) -> bool {
+            true
+        }
+    }
+
+    #[derive(Debug)]
+    struct LooseWalls;
+
+    impl RuleSet for LooseWalls {
+        fn name(&self) -> &str {
+            "loose-walls"
+        }
+
+        fn is_wall_connected(&self) -> bool {
+            false
+        }
+    }
+
+    #[test]
+    fn test_pools_allowed() {
+        let solver = Solver::new(vec![]);
+        let grid = Grid::from_str("BB\nBB").unwrap();
+        assert_eq!(
+            solver.detect_contradictions(&grid),
+            Err(SolverError::Contradiction)
+        );
+        let grid = grid.with_rules(PoolsAllowed);
+        assert_eq!(solver.detect_contradictions(&grid), Ok(()));
+
+        let mut grid = Grid::from_str("1..\n...\n..1")
+            .unwrap()
+            .with_rules(PoolsAllowed);
+        let solver = SolverConfig::profile("full").unwrap().build().unwrap();
+        assert_eq!(solver.solve(&mut grid), Ok(()));
+        // Custom rule sets have no header to be parsed back from
+        assert_eq!(grid.to_input_string(), "1BB\nBBB\nBB1");
+    }
+
+    #[test]
+    fn test_loose_walls() {
+        let solver = Solver::new(vec![]);
+        let grid = Grid::from_str("B1B").unwrap();
+        assert_eq!(
+            solver.detect_contradictions(&grid),
+            Err(SolverError::Contradiction)
+        );
+        assert_eq!(
+            solver.detect_contradictions(&grid.with_rules(LooseWalls)),
+            Ok(())
+        );
+    }
+}
@@ -0,0 +1,441 @@
+use std::fmt::Debug;
+
+use super::zobrist::hash_words;
+use crate::Coord;
+
+/// How the cells of a board are connected: which cells exist, which are neighbors, and which
+/// groups of cells form a pool.
+///
+/// Cells are addressed by their [`Coord`] in the smallest rectangle of `num_rows` by `num_cols`
+/// cells containing the board, and stored in row-major order. Cells of the rectangle that are not
+/// part of the board are void: they are never marked and aren't neighbors of any cell.
+///
+/// The grid asks the topology once, when it is created, and keeps the answers in lookup tables.
+pub trait Topology: Debug + Send + Sync {
+    fn num_rows(&self) -> usize;
+    fn num_cols(&self) -> usize;
+
+    /// Returns `true` if the cell is part of the board.
+    fn contains(&self, _coord: Coord) -> bool {
+        true
+    }
+
+    /// Returns the cells adjacent to the given cell. Regions are made of adjacent cells. The
+    /// relation must be symmetric, and only contain cells that are part of the board.
+    fn neighbors(&self, coord: Coord) -> Vec<Coord>;
+
+    /// Returns the cells touching the given cell, including ones that only share a corner, for
+    /// rules about diagonal connectivity. Defaults to the neighbors.
+    fn surrounding(&self, coord: Coord) -> Vec<Coord> {
+        self.neighbors(coord)
+    }
+
+    /// Returns the groups of cells that may not all be black, e.g. 2x2 squares. Only groups that
+    /// lie entirely on the board are returned.
+    fn pools(&self) -> Vec<Vec<Coord>>;
+
+    /// Returns the built-in board this topology is, if any. Whole-board checks on such boards run
+    /// as bitset shifts rather than by walking the lookup tables.
+    fn as_board(&self) -> Option<&Board> {
+        None
+    }
+}
+
+/// How the cells of a board are arranged.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Layout {
+    /// Square cells with four neighbors each. Pools are 2x2 squares.
+    #[default]
+    Square,
+    /// Hexagonal cells with six neighbors each, in rows where every odd row is shifted right by
+    /// half a cell. Pools are the three cells around a vertex.
+    Hex,
+}
+
+impl Layout {
+    /// Returns the offsets of the neighbors of a cell in the given row.
+    fn neighbor_offsets(self, row: usize) -> &'static [(isize, isize)] {
+        match self {
+            Layout::Square => &[(-1, 0), (1, 0), (0, -1), (0, 1)],
+            Layout::Hex if row % 2 == 0 => &[(0, -1), (0, 1), (-1, -1), (-1, 0), (1, -1), (1, 0)],
+            Layout::Hex => &[(0, -1), (0, 1), (-1, 0), (-1, 1), (1, 0), (1, 1)],
+        }
+    }
+
+    /// Returns the offsets of the cells of the pools whose first cell is in the given row. The
+    /// first offset of every pool is `(0, 0)`.
+    fn pool_offsets(self, row: usize) -> &'static [&'static [(isize, isize)]] {
+        match self {
+            Layout::Square => &[&[(0, 0), (0, 1), (1, 0), (1, 1)]],
+            // The cell, its right neighbor and the neighbor they share below, or the cell and its
+            // two neighbors below
+            Layout::Hex if row % 2 == 0 => &[&[(0, 0), (0, 1), (1, 0)], &[(0, 0), (1, -1), (1, 0)]],
+            Layout::Hex => &[&[(0, 0), (0, 1), (1, 1)], &[(0, 0), (1, 0), (1, 1)]],
+        }
+    }
+}
+
+/// The shape of a board, beyond its number of rows and columns.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BoardOptions {
+    /// Cells that are not part of the board, for boards with holes or irregular outlines. They
+    /// are neither black nor white.
+    pub void_cells: Vec<Coord>,
+    /// Whether the edges of the board wrap around, i.e. the board is a torus. Rules that look at
+    /// the bounding boxes of regions (rectangular islands and strip walls) are not supported on
+    /// wrapping boards.
+    pub wrap: bool,
+    /// The arrangement of the cells. Rules that look at the bounding boxes of regions are only
+    /// supported on square cells.
+    pub layout: Layout,
+}
+
+/// The built-in topology: a rectangle of square or hex cells, possibly with void cells and
+/// wrapping edges.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Board {
+    num_rows: usize,
+    num_cols: usize,
+    /// For every cell, whether it lies outside the board
+    void: Box<[bool]>,
+    /// Whether the edges of the board wrap around
+    wrap: bool,
+    layout: Layout,
+}
+
+impl Board {
+    /// Creates a board of the given size and shape.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a wrapping hex board has an odd number of rows, as its first and last rows would
+    /// be shifted the same way, or if a void cell lies outside the board.
+    pub fn new(num_rows: usize, num_cols: usize, options: BoardOptions) -> Self {
+        assert!(
+            !(options.wrap && options.layout == Layout::Hex && num_rows % 2 != 0),
+            "wrapping hex boards must have an even number of rows"
+        );
+
+        let mut void = vec![false; num_rows * num_cols];
+        for coord in options.void_cells {
+            assert!(
+                coord.row < num_rows && coord.col < num_cols,
+                "void cell {:?} lies outside the board",
+                coord
+            );
+            void[coord.row * num_cols + coord.col] = true;
+        }
+
+        Self {
+            num_rows,
+            num_cols,
+            void: void.into(),
+            wrap: options.wrap,
+            layout: options.layout,
+        }
+    }
+
+    /// Returns `true` if the edges of the board wrap around.
+    pub fn is_wrapping(&self) -> bool {
+        self.wrap
+    }
+
+    pub fn layout(&self) -> Layout {
+        self.layout
+    }
+
+    /// Returns the void cells and whether the board wraps, e.g. to create a board of the same
+    /// shape.
+    pub fn options(&self) -> BoardOptions {
+        BoardOptions {
+            void_cells: (0..self.void.len())
+                .filter(|&index| self.void[index])
+                .map(|index| Coord::new(index / self.num_cols, index % self.num_cols))
+                .collect(),
+            wrap: self.wrap,
+            layout: self.layout,
+        }
+    }
+
+    /// Returns the cell at the given offset from `coord`, wrapping around the edges of the board if
+    /// it wraps. Returns `None` if the cell lies outside the rectangle.
+    fn offset(&self, coord: Coord, d_row: isize, d_col: isize) -> Option<Coord> {
+        let row = coord.row as isize + d_row;
+        let col = coord.col as isize + d_col;
+        let (num_rows, num_cols) = (self.num_rows as isize, self.num_cols as isize);
+        if self.wrap {
+            Some(Coord::new(
+                row.rem_euclid(num_rows) as usize,
+                col.rem_euclid(num_cols) as usize,
+            ))
+        } else if row >= 0 && row < num_rows && col >= 0 && col < num_cols {
+            Some(Coord::new(row as usize, col as usize))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the cells at the given offsets from `coord` that are part of the board.
+    fn offsets(
+        &self,
+        coord: Coord,
+        offsets: impl IntoIterator<Item = (isize, isize)>,
+    ) -> Vec<Coord> {
+        let mut coords = vec![];
+        for adj_coord in offsets
+            .into_iter()
+            .filter_map(|(d_row, d_col)| self.offset(coord, d_row, d_col))
+        {
+            // Small wrapping boards can reach the same cell from several sides
+            if adj_coord != coord && self.contains(adj_coord) && !coords.contains(&adj_coord) {
+                coords.push(adj_coord);
+            }
+        }
+        coords
+    }
+}
+
+impl Topology for Board {
+    fn num_rows(&self) -> usize {
+        self.num_rows
+    }
+
+    fn num_cols(&self) -> usize {
+        self.num_cols
+    }
+
+    fn contains(&self, coord: Coord) -> bool {
+        !self.void[coord.row * self.num_cols + coord.col]
+    }
+
+    fn neighbors(&self, coord: Coord) -> Vec<Coord> {
+        self.offsets(
+            coord,
+            self.layout.neighbor_offsets(coord.row).iter().copied(),
+        )
+    }
+
+    /// Returns the (up to) eight cells surrounding the given cell, including diagonal ones. Hex
+    /// cells have no diagonal neighbors, so these are just their neighbors.
+    fn surrounding(&self, coord: Coord) -> Vec<Coord> {
+        match self.layout {
+            Layout::Square => self.offsets(
+                coord,
+                (-1..=1).flat_map(|d_row| (-1..=1).map(move |d_col| (d_row, d_col))),
+            ),
+            Layout::Hex => self.neighbors(coord),
+        }
+    }
+
+    /// Returns the 2x2 squares (or the three cells around a vertex on hex boards), including the
+    /// ones that cross the edges of a wrapping board.
+    fn pools(&self) -> Vec<Vec<Coord>> {
+        (0..self.num_rows)
+            .flat_map(|row| (0..self.num_cols).map(move |col| Coord::new(row, col)))
+            .flat_map(|coord| {
+                self.layout
+                    .pool_offsets(coord.row)
+                    .iter()
+                    .filter_map(move |offsets| {
+                        offsets
+                            .iter()
+                            .map(|&(d_row, d_col)| self.offset(coord, d_row, d_col))
+                            .collect::<Option<Vec<_>>>()
+                    })
+            })
+            .filter(|pool| pool.iter().all(|&coord| self.contains(coord)))
+            .collect()
+    }
+
+    fn as_board(&self) -> Option<&Board> {
+        Some(self)
+    }
+}
+
+/// Lookup tables of a [`Topology`], indexed like the cells of the grid.
+#[derive(Debug)]
+pub(crate) struct Adjacency {
+    pub(crate) void: Box<[bool]>,
+    pub(crate) neighbors: Box<[Box<[Coord]>]>,
+    pub(crate) surrounding: Box<[Box<[Coord]>]>,
+    pub(crate) pools: Box<[Box<[Coord]>]>,
+    /// Number of cells on the board, i.e. excluding void cells
+    pub(crate) num_cells: usize,
+    /// Hash of the shape of the board, see [`board_key`]
+    pub(crate) key: u64,
+}
+
+impl Adjacency {
+    pub(crate) fn new(topology: &dyn Topology) -> Self {
+        let coords = (0..topology.num_rows())
+            .flat_map(|row| (0..topology.num_cols()).map(move |col| Coord::new(row, col)))
+            .collect::<Vec<_>>();
+        let void = coords
+            .iter()
+            .map(|&coord| !topology.contains(coord))
+            .collect::<Box<[_]>>();
+        // Void cells have no neighbors of their own
+        let table = |f: &dyn Fn(Coord) -> Vec<Coord>| {
+            coords
+                .iter()
+                .zip(&void)
+                .map(|(&coord, &is_void)| {
+                    if is_void {
+                        Box::default()
+                    } else {
+                        f(coord).into()
+                    }
+                })
+                .collect::<Box<[_]>>()
+        };
+
+        let neighbors = table(&|coord| topology.neighbors(coord));
+        let surrounding = table(&|coord| topology.surrounding(coord));
+        let pools = topology
+            .pools()
+            .into_iter()
+            .map(Vec::into)
+            .collect::<Box<[_]>>();
+        let key = board_key(
+            topology.num_rows(),
+            topology.num_cols(),
+            &[&neighbors[..], &surrounding[..], &pools[..]],
+        );
+
+        Self {
+            neighbors,
+            surrounding,
+            pools,
+            num_cells: void.iter().filter(|&&is_void| !is_void).count(),
+            void,
+            key,
+        }
+    }
+}
+
+/// Returns a hash of the size of the board and its lookup tables, which covers void cells,
+/// wrapping edges and the layout alike.
+fn board_key(num_rows: usize, num_cols: usize, tables: &[&[Box<[Coord]>]]) -> u64 {
+    let words = tables.iter().flat_map(|table| {
+        // Separate the entries, so that tables with the same cells in different groups differ
+        table.iter().flat_map(|entry| {
+            entry
+                .iter()
+                .map(|coord| ((coord.row as u64) << 32) | coord.col as u64)
+                .chain([u64::MAX])
+        })
+    });
+    hash_words([num_rows as u64, num_cols as u64].into_iter().chain(words))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::{Board, BoardOptions, Topology};
+    use crate::config::SolverConfig;
+    use crate::strategy::avoid_pools::AvoidPools;
+    use crate::strategy::Strategy;
+    use crate::{Coord, Grid, State};
+
+    /// A board whose left and right edges are joined, but whose top and bottom edges are not.
+    #[derive(Debug)]
+    struct Cylinder {
+        num_rows: usize,
+        num_cols: usize,
+    }
+
+    impl Cylinder {
+        fn wrap(&self, row: usize, col: isize) -> Coord {
+            Coord::new(row, col.rem_euclid(self.num_cols as isize) as usize)
+        }
+    }
+
+    impl Topology for Cylinder {
+        fn num_rows(&self) -> usize {
+            self.num_rows
+        }
+
+        fn num_cols(&self) -> usize {
+            self.num_cols
+        }
+
+        fn neighbors(&self, coord: Coord) -> Vec<Coord> {
+            let col = coord.col as isize;
+            let mut neighbors = vec![self.wrap(coord.row, col - 1), self.wrap(coord.row, col + 1)];
+            if coord.row > 0 {
+                neighbors.push(Coord::new(coord.row - 1, coord.col));
+            }
+            if coord.row + 1 < self.num_rows {
+                neighbors.push(Coord::new(coord.row + 1, coord.col));
+            }
+            neighbors
+        }
+
+        fn pools(&self) -> Vec<Vec<Coord>> {
+            (0..self.num_rows - 1)
+                .flat_map(|row| (0..self.num_cols).map(move |col| (row, col as isize)))
+                .map(|(row, col)| {
+                    vec![
+                        self.wrap(row, col),
+                        self.wrap(row, col + 1),
+                        self.wrap(row + 1, col),
+                        self.wrap(row + 1, col + 1),
+                    ]
+                })
+                .collect()
+        }
+    }
+
+    /// Parses the grid, but with its left and right edges joined.
+    fn cylinder(input: &str) -> Grid {
+        let parsed = Grid::from_str(input).unwrap();
+        let topology = Cylinder {
+            num_rows: parsed.num_rows,
+            num_cols: parsed.num_cols,
+        };
+        let givens = parsed.iter().filter_map(|(coord, cell)| match cell.state {
+            Some(State::Numbered(number)) => Some((coord, number)),
+            _ => None,
+        });
+        let mut grid = Grid::with_topology(topology, givens);
+        for (coord, cell) in parsed.iter() {
+            if let Some(state @ (State::White | State::Black)) = cell.state {
+                grid.mark_cell(coord, state).unwrap();
+            }
+        }
+        grid
+    }
+
+    #[test]
+    fn test_avoid_pools() {
+        // The pool crosses the left and right edges
+        let mut grid = cylinder("B..B\n...B\n.9..");
+        assert!(AvoidPools.apply(&mut grid).unwrap());
+        assert_eq!(grid.to_input_string(), "B..B\nW..B\n.9..");
+    }
+
+    #[test]
+    fn test_solve() {
+        // Unsolvable with hard edges, since the island of the top-left 3 crosses them
+        let mut grid = cylinder(".....\n3..3.\n.2...\n.....");
+        SolverConfig::profile("full")
+            .unwrap()
+            .build()
+            .unwrap()
+            .solve(&mut grid)
+            .unwrap();
+        assert_eq!(grid.to_input_string(), "WWBBB\n3BB3B\nB2BWW\nBWBBB");
+    }
+
+    #[test]
+    #[should_panic(expected = "lies outside the board")]
+    fn test_void_cell_outside() {
+        // Would otherwise void (1, 2)
+        let options = BoardOptions {
+            void_cells: vec![Coord::new(0, 5)],
+            ..Default::default()
+        };
+        Board::new(3, 3, options);
+    }
+}
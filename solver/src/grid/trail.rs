@@ -0,0 +1,249 @@
+use std::mem;
+use std::thread;
+
+use crate::grid::zobrist::zobrist_key;
+use crate::grid::{Cell, Region, RegionID};
+use crate::{Coord, Grid, State};
+
+/// A point in a grid's history that it can be rolled back to, created by [`Grid::checkpoint`].
+///
+/// Checkpoints must be released (by [`Grid::rollback`] or [`Grid::commit`]) in the reverse order
+/// of their creation. Dropping a checkpoint instead would leave the grid recording its changes, so
+/// it panics in debug builds.
+#[must_use]
+#[derive(Debug)]
+pub struct Checkpoint {
+    trail_len: usize,
+}
+
+impl Drop for Checkpoint {
+    fn drop(&mut self) {
+        // Don't turn a panic while the checkpoint was active into an abort
+        debug_assert!(
+            thread::panicking(),
+            "checkpoint dropped without rollback or commit"
+        );
+    }
+}
+
+/// Changes made to a grid while at least one checkpoint is active, in the order they were made.
+///
+/// Clones of a grid start without any history, so a checkpoint can only be rolled back on the grid
+/// that created it.
+#[derive(Default)]
+pub(crate) struct Trail {
+    entries: Vec<TrailEntry>,
+    num_checkpoints: usize,
+}
+
+impl Clone for Trail {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+pub(crate) enum TrailEntry {
+    /// A previously unknown cell was marked, creating a new region rooted at that cell.
+    CellMarked(Coord),
+    /// An unknown cell was removed from a region's unknowns.
+    UnknownRemoved { region_id: RegionID, coord: Coord },
+    /// The `absorbed` region was fused into the region with ID `region_id`, which used to have
+    /// state `prev_state` and `prev_len` cells ending with the cell at index `prev_last`, and
+    /// gained the unknowns in `added_unknowns`.
+    RegionsFused {
+        region_id: RegionID,
+        absorbed_id: RegionID,
+        absorbed: Region,
+        prev_state: State,
+        prev_last: usize,
+        prev_len: usize,
+        added_unknowns: Vec<Coord>,
+    },
+}
+
+impl Trail {
+    pub(crate) fn is_recording(&self) -> bool {
+        self.num_checkpoints > 0
+    }
+
+    pub(crate) fn record(&mut self, entry: TrailEntry) {
+        if self.is_recording() {
+            self.entries.push(entry);
+        }
+    }
+}
+
+impl Grid {
+    /// Starts recording changes to the grid, so they can be undone with [`Grid::rollback`].
+    pub fn checkpoint(&mut self) -> Checkpoint {
+        self.trail.num_checkpoints += 1;
+        Checkpoint {
+            trail_len: self.trail.entries.len(),
+        }
+    }
+
+    /// Undoes all changes made since the checkpoint was created.
+    pub fn rollback(&mut self, checkpoint: Checkpoint) {
+        assert!(self.trail.entries.len() >= checkpoint.trail_len);
+        while self.trail.entries.len() > checkpoint.trail_len {
+            let entry = self.trail.entries.pop().unwrap();
+            self.undo(entry);
+        }
+        self.invalidate_analysis();
+        self.release(checkpoint);
+    }
+
+    /// Keeps all changes made since the checkpoint was created. They can still be undone by rolling
+    /// back an enclosing checkpoint.
+    pub fn commit(&mut self, checkpoint: Checkpoint) {
+        self.release(checkpoint);
+    }
+
+    fn release(&mut self, checkpoint: Checkpoint) {
+        mem::forget(checkpoint);
+        assert!(self.trail.num_checkpoints > 0);
+        self.trail.num_checkpoints -= 1;
+        if self.trail.num_checkpoints == 0 {
+            self.trail.entries.clear();
+        }
+    }
+
+    /// Returns the cells marked since the checkpoint was created, with their current state.
+    pub(crate) fn marks_since(&self, checkpoint: &Checkpoint) -> Vec<(Coord, State)> {
+        self.trail.entries[checkpoint.trail_len..]
+            .iter()
+            .filter_map(|entry| match *entry {
+                TrailEntry::CellMarked(coord) => Some((coord, self.cell(coord).state.unwrap())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn undo(&mut self, entry: TrailEntry) {
+        match entry {
+            TrailEntry::CellMarked(coord) => {
+                let index = self.coord_to_index(coord);
+                self.hash ^= zobrist_key(index, self.cells[index].state.unwrap());
+                self.cells[index] = Cell::default();
+                self.regions[index] = None;
+                let change = self.changes.pop();
+                debug_assert_eq!(change, Some(coord));
+            }
+            TrailEntry::UnknownRemoved { region_id, coord } => {
+                let region = self.region_mut(region_id).unwrap();
+                region.unknowns.insert(coord);
+            }
+            TrailEntry::RegionsFused {
+                region_id,
+                absorbed_id,
+                absorbed,
+                prev_state,
+                prev_last,
+                prev_len,
+                added_unknowns,
+            } => {
+                // The link from the previous last cell is left dangling, as it is past the end of
+                // the list
+                let region = self.region_mut(region_id).unwrap();
+                region.state = prev_state;
+                region.last = prev_last;
+                region.len = prev_len;
+                for coord in added_unknowns {
+                    region.unknowns.remove(&coord);
+                }
+
+                self.cells[absorbed_id.to_index()].parent = absorbed_id.to_index();
+                self.regions[absorbed_id.to_index()] = Some(absorbed);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::{Coord, Grid, State};
+
+    /// Returns a description of the grid's cells and regions that should survive a rollback.
+    fn snapshot(grid: &Grid) -> (String, Vec<String>) {
+        let regions = grid
+            .regions_iter()
+            .map(|(region_id, region)| {
+                let coords = grid.region_coords(region).collect::<Vec<_>>();
+                format!("{:?} {:?} {:?}", region_id, region, coords)
+            })
+            .collect();
+        (grid.to_input_string(), regions)
+    }
+
+    #[test]
+    fn test_rollback() {
+        let mut grid = Grid::from_str("3..\n.B.\n..2").unwrap();
+        let before = snapshot(&grid);
+        let num_changes = grid.num_changes();
+
+        let checkpoint = grid.checkpoint();
+        grid.mark_cell(Coord::new(0, 1), State::White).unwrap();
+        grid.mark_cell(Coord::new(1, 0), State::Black).unwrap();
+        grid.mark_cell(Coord::new(1, 2), State::Black).unwrap();
+        assert_eq!(
+            grid.changes_since(num_changes),
+            [Coord::new(0, 1), Coord::new(1, 0), Coord::new(1, 2)]
+        );
+        assert_eq!(
+            grid.marks_since(&checkpoint),
+            [
+                (Coord::new(0, 1), State::White),
+                (Coord::new(1, 0), State::Black),
+                (Coord::new(1, 2), State::Black),
+            ]
+        );
+        grid.rollback(checkpoint);
+
+        assert_eq!(snapshot(&grid), before);
+        assert_eq!(grid.num_changes(), num_changes);
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "checkpoint dropped without rollback or commit")]
+    fn test_dropped_checkpoint() {
+        let mut grid = Grid::from_str("3..\n.B.\n..2").unwrap();
+        let _ = grid.checkpoint();
+    }
+
+    #[test]
+    fn test_nested_rollback() {
+        let mut grid = Grid::from_str("3..\n.B.\n..2").unwrap();
+        let before = snapshot(&grid);
+
+        let outer = grid.checkpoint();
+        grid.mark_cell(Coord::new(0, 1), State::White).unwrap();
+        let after_outer = snapshot(&grid);
+
+        let inner = grid.checkpoint();
+        grid.mark_cell(Coord::new(0, 2), State::White).unwrap();
+        grid.rollback(inner);
+        assert_eq!(snapshot(&grid), after_outer);
+
+        let inner = grid.checkpoint();
+        grid.mark_cell(Coord::new(2, 0), State::Black).unwrap();
+        grid.commit(inner);
+
+        grid.rollback(outer);
+        assert_eq!(snapshot(&grid), before);
+    }
+
+    #[test]
+    fn test_rollback_after_contradiction() {
+        let mut grid = Grid::from_str("1.1").unwrap();
+        let before = snapshot(&grid);
+
+        let checkpoint = grid.checkpoint();
+        assert!(grid.mark_cell(Coord::new(0, 1), State::White).is_err());
+        grid.rollback(checkpoint);
+
+        assert_eq!(snapshot(&grid), before);
+    }
+}
@@ -0,0 +1,115 @@
+use crate::{Grid, State};
+
+/// Returns the Zobrist key for a cell with the given index and state. Keys are derived from the
+/// index and state with a mixing function, so no table of random keys has to be stored.
+pub(crate) fn zobrist_key(index: usize, state: State) -> u64 {
+    let state_index = match state {
+        State::Black => 0,
+        State::White => 1,
+        State::Numbered(None) => 2,
+        State::Numbered(Some(number)) => 3 + number as u64,
+    };
+    splitmix64(((index as u64) << 32) ^ state_index)
+}
+
+/// Combines the words into a single hash, e.g. to describe everything about a grid besides its cell
+/// states.
+pub(crate) fn hash_words(words: impl IntoIterator<Item = u64>) -> u64 {
+    words
+        .into_iter()
+        .fold(0, |hash, word| splitmix64(hash ^ splitmix64(word)))
+}
+
+/// The SplitMix64 finalizer, which maps distinct inputs to well-distributed outputs.
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+impl Grid {
+    /// Returns a hash of the states of all cells, which is kept up to date as cells are marked.
+    /// Grids with the same cell states have the same hash, regardless of the order in which cells
+    /// were marked. The hash also covers the shape of the board and the rules, so grids of
+    /// different puzzles don't share hashes just because they mark the same cells.
+    pub fn zobrist_hash(&self) -> u64 {
+        self.hash ^ self.adjacency.key ^ self.rules.key()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::{Coord, Grid, RuleSet, State};
+
+    #[test]
+    fn test_order_independent() {
+        let grid = Grid::from_str("3..\n...\n..2").unwrap();
+
+        let mut grid_1 = grid.clone();
+        grid_1.mark_cell(Coord::new(0, 1), State::White).unwrap();
+        grid_1.mark_cell(Coord::new(1, 1), State::Black).unwrap();
+
+        let mut grid_2 = grid.clone();
+        grid_2.mark_cell(Coord::new(1, 1), State::Black).unwrap();
+        grid_2.mark_cell(Coord::new(0, 1), State::White).unwrap();
+
+        assert_eq!(grid_1.zobrist_hash(), grid_2.zobrist_hash());
+        assert_ne!(grid_1.zobrist_hash(), grid.zobrist_hash());
+        assert_eq!(
+            grid_1.zobrist_hash(),
+            Grid::from_str("3W.\n.B.\n..2").unwrap().zobrist_hash()
+        );
+    }
+
+    #[test]
+    fn test_rollback() {
+        let mut grid = Grid::from_str("3..\n...\n..2").unwrap();
+        let hash = grid.zobrist_hash();
+
+        let checkpoint = grid.checkpoint();
+        grid.mark_cell(Coord::new(0, 1), State::Black).unwrap();
+        assert_ne!(grid.zobrist_hash(), hash);
+        grid.rollback(checkpoint);
+
+        assert_eq!(grid.zobrist_hash(), hash);
+    }
+
+    #[test]
+    fn test_board() {
+        let hash = |input: &str| Grid::from_str(input).unwrap().zobrist_hash();
+        assert_ne!(hash("1..."), hash("1..-"));
+        assert_ne!(hash("1..."), hash("# wrap: true\n1..."));
+        assert_ne!(hash("1..."), hash("# variant: mochikoro\n1..."));
+        assert_ne!(hash("1...\n...."), hash("1.\n..\n..\n.."));
+    }
+
+    #[test]
+    fn test_rules_key() {
+        /// Rule sets that share a name, but not their pool rule
+        #[derive(Debug)]
+        struct Pools(bool);
+
+        impl RuleSet for Pools {
+            fn name(&self) -> &str {
+                "pools"
+            }
+
+            fn allows_pools(&self) -> bool {
+                self.0
+            }
+
+            fn key(&self) -> u64 {
+                self.0 as u64
+            }
+        }
+
+        let grid = Grid::from_str("1...").unwrap();
+        assert_ne!(
+            grid.clone().with_rules(Pools(false)).zobrist_hash(),
+            grid.with_rules(Pools(true)).zobrist_hash()
+        );
+    }
+}
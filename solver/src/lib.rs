@@ -1,22 +1,34 @@
+pub use context::{Budget, Progress, ProgressReporter, SolveContext};
 pub use grid::*;
 use strategy::Strategy;
 
+pub mod config;
+mod context;
+pub mod generator;
 mod grid;
+pub mod minimizer;
 pub mod strategy;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SolverError {
     Contradiction,
     NoStrategyApplies,
+    /// The solve was cancelled or exceeded its [`Budget`].
+    BudgetExceeded,
 }
 
 pub struct Solver {
     strategies: Vec<Box<dyn Strategy>>,
+    budget: Budget,
 }
 
 impl Solver {
     pub fn new(strategies: Vec<Box<dyn Strategy>>) -> Self {
-        Self { strategies }
+        Self::with_budget(strategies, Budget::default())
+    }
+
+    pub fn with_budget(strategies: Vec<Box<dyn Strategy>>, budget: Budget) -> Self {
+        Self { strategies, budget }
     }
 
     pub fn solve(&self, grid: &mut Grid) -> Result<(), SolverError> {
@@ -24,16 +36,62 @@ impl Solver {
     }
 
     pub fn solve_with_logger(
+        &self,
+        grid: &mut Grid,
+        logger: impl SolverLogger,
+    ) -> Result<(), SolverError> {
+        let context = SolveContext::new(self.budget.clone());
+        self.solve_with_context(grid, logger, &context)
+    }
+
+    /// Solves the grid with the given context, e.g. to report progress or to share the budget of
+    /// an enclosing solve. The solver's own budget is ignored in favor of the one in the context.
+    pub fn solve_with_context(
         &self,
         grid: &mut Grid,
         mut logger: impl SolverLogger,
+        context: &SolveContext,
+    ) -> Result<(), SolverError> {
+        let result = self.solve_loop(grid, &mut logger, context);
+        if context.depth() == 0 {
+            context.update_decided_cells(grid);
+            context.report_progress(true);
+        }
+        result
+    }
+
+    fn solve_loop(
+        &self,
+        grid: &mut Grid,
+        logger: &mut impl SolverLogger,
+        context: &SolveContext,
     ) -> Result<(), SolverError> {
+        // Number of changes the grid had when each strategy was last applied
+        let mut last_applied = vec![None; self.strategies.len()];
+
         'outer: while !grid.is_complete() {
+            context.update_decided_cells(grid);
+            context.check()?;
             logger.before_apply(grid);
 
-            for strategy in &self.strategies {
-                if strategy.apply(grid)? {
+            for (strategy, last_applied) in self.strategies.iter().zip(&mut last_applied) {
+                let num_changes = grid.num_changes();
+                let applied = match *last_applied {
+                    // Nothing changed since the strategy last found nothing to mark
+                    Some(last_num_changes) if last_num_changes == num_changes => continue,
+                    Some(last_num_changes) => {
+                        let changed = grid.changes_since(last_num_changes).to_vec();
+                        strategy
+                            .apply_incremental(grid, &changed)
+                            .unwrap_or_else(|| strategy.apply_with_context(grid, context))
+                    }
+                    None => strategy.apply_with_context(grid, context),
+                };
+                *last_applied = Some(num_changes);
+
+                if applied? {
                     logger.strategy_applied(grid, strategy.name());
+                    context.count_application()?;
                     continue 'outer;
                 }
             }
@@ -59,7 +117,21 @@ impl Solver {
             }
         }
 
-        Ok(())
+        let rules = grid.rules();
+        if !rules.allows_pools() && grid.bit_grid().has_pool() {
+            return Err(SolverError::Contradiction);
+        }
+        if rules.has_rectangular_islands() {
+            grid.detect_rectangle_contradictions()?;
+        }
+        if rules.is_white_diagonally_connected() && !grid.is_white_diagonally_connectable() {
+            return Err(SolverError::Contradiction);
+        }
+        if rules.has_strip_walls() {
+            grid.detect_strip_contradictions()?;
+        }
+
+        rules.detect_contradictions(grid)
     }
 }
 
@@ -90,6 +162,13 @@ impl DisplayLogger {
     }
 }
 
+#[cfg(feature = "display")]
+impl Default for DisplayLogger {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[cfg(feature = "display")]
 impl SolverLogger for DisplayLogger {
     fn before_apply(&mut self, grid: &Grid) {
@@ -110,3 +189,319 @@ impl SolverLogger for DisplayLogger {
         println!("{}", grid);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+
+    use crate::config::SolverConfig;
+    use crate::strategy::{
+        avoid_pools::AvoidPools, complete_islands::CompleteIslands, confinement::Confinement,
+        dual_liberties::DualLiberties, hypotheticals::Hypotheticals,
+        single_liberties::SingleLiberties, unreachable_cells::UnreachableCells, Strategy,
+    };
+    use crate::{
+        BoardOptions, Budget, Grid, NoopLogger, Progress, SolveContext, Solver, SolverError,
+        SolverLogger, Variant,
+    };
+
+    // https://en.wikipedia.org/wiki/Nurikabe_(puzzle)
+    const WIKIPEDIA: &str = concat!(
+        "2........2\n",
+        "......2...\n",
+        ".2..7.....\n",
+        "..........\n",
+        "......3.3.\n",
+        "..2....3..\n",
+        "2..4......\n",
+        "..........\n",
+        ".1....2.4.",
+    );
+
+    /// Applies the strategies the way the solver would without incremental scheduling: every
+    /// strategy is fully reapplied after every change.
+    fn solve_from_scratch(
+        strategies: &[Box<dyn Strategy>],
+        grid: &mut Grid,
+        mut logger: &mut RecordingLogger,
+    ) {
+        'outer: while !grid.is_complete() {
+            for strategy in strategies {
+                if strategy.apply(grid).unwrap() {
+                    logger.strategy_applied(grid, strategy.name());
+                    continue 'outer;
+                }
+            }
+            panic!("no strategy applies");
+        }
+    }
+
+    #[derive(Default)]
+    struct RecordingLogger(Vec<(String, String)>);
+
+    impl SolverLogger for &mut RecordingLogger {
+        fn before_apply(&mut self, _grid: &Grid) {}
+
+        fn strategy_applied(&mut self, grid: &Grid, strategy_name: &str) {
+            self.0
+                .push((strategy_name.to_string(), grid.to_input_string()));
+        }
+
+        fn no_strategy_applies(&mut self, _grid: &Grid) {}
+    }
+
+    fn strategies() -> Vec<Box<dyn Strategy>> {
+        let basic_strategies = || -> Vec<Box<dyn Strategy>> {
+            vec![
+                Box::new(CompleteIslands),
+                Box::new(SingleLiberties),
+                Box::new(DualLiberties),
+                Box::new(AvoidPools),
+                Box::new(UnreachableCells),
+            ]
+        };
+        let mut strategies = basic_strategies();
+        strategies.push(Box::new(Confinement));
+        strategies.push(Box::new(Hypotheticals::new(basic_strategies())));
+        strategies
+    }
+
+    #[test]
+    fn test_incremental_matches_full() {
+        let grid = Grid::from_str(WIKIPEDIA).unwrap();
+
+        let mut incremental = RecordingLogger::default();
+        Solver::new(strategies())
+            .solve_with_logger(&mut grid.clone(), &mut incremental)
+            .unwrap();
+
+        let mut full = RecordingLogger::default();
+        solve_from_scratch(&strategies(), &mut grid.clone(), &mut full);
+
+        assert_eq!(incremental.0, full.0);
+    }
+
+    /// Solves the grid within the given budget, and checks that the solve can be resumed from
+    /// where it stopped.
+    fn solve_with_budget(budget: Budget) -> Grid {
+        let mut grid = Grid::from_str(WIKIPEDIA).unwrap();
+        assert_eq!(
+            Solver::with_budget(strategies(), budget).solve(&mut grid),
+            Err(SolverError::BudgetExceeded)
+        );
+
+        let mut resumed_grid = grid.clone();
+        Solver::new(strategies()).solve(&mut resumed_grid).unwrap();
+        grid
+    }
+
+    #[test]
+    fn test_max_applications() {
+        let grid = solve_with_budget(Budget {
+            max_applications: Some(3),
+            ..Default::default()
+        });
+        assert_ne!(grid.to_input_string(), WIKIPEDIA);
+    }
+
+    #[test]
+    fn test_max_hypotheses() {
+        let grid = solve_with_budget(Budget {
+            max_hypotheses: Some(1),
+            ..Default::default()
+        });
+        assert_ne!(grid.to_input_string(), WIKIPEDIA);
+    }
+
+    #[test]
+    fn test_deadline() {
+        let grid = solve_with_budget(Budget {
+            deadline: Some(Instant::now()),
+            ..Default::default()
+        });
+        assert_eq!(grid.to_input_string(), WIKIPEDIA);
+    }
+
+    #[test]
+    fn test_cancel() {
+        let grid = solve_with_budget(Budget {
+            cancel: Some(Arc::new(AtomicBool::new(true))),
+            ..Default::default()
+        });
+        assert_eq!(grid.to_input_string(), WIKIPEDIA);
+    }
+
+    #[test]
+    fn test_question_mark_clue() {
+        let input = WIKIPEDIA.replace("..2....3..", "..2....?..");
+        let mut grid = Grid::from_str(&input).unwrap();
+        assert_eq!(grid.to_input_string(), input);
+        Solver::new(strategies()).solve(&mut grid).unwrap();
+
+        let mut expected = Grid::from_str(WIKIPEDIA).unwrap();
+        Solver::new(strategies()).solve(&mut expected).unwrap();
+        assert_eq!(
+            grid.to_input_string(),
+            expected.to_input_string().replace("BBBB3BB", "BBBB?BB")
+        );
+    }
+
+    #[test]
+    fn test_mochikoro() {
+        // Has a unique solution, checked by brute force
+        let mut grid =
+            Grid::from_str("# variant: mochikoro\n4..1.\n.....\n.....\n.....\n1.1.1").unwrap();
+        SolverConfig::profile("mochikoro")
+            .unwrap()
+            .build()
+            .unwrap()
+            .solve(&mut grid)
+            .unwrap();
+        assert_eq!(
+            grid.to_input_string(),
+            "# variant: mochikoro\n4WB1B\nWWBBW\nBBWBW\nBWBWB\n1B1B1"
+        );
+    }
+
+    #[test]
+    fn test_nuribou() {
+        // Has a unique solution, checked by brute force
+        let mut grid =
+            Grid::from_str("# variant: nuribou\n.1..6\n..2..\n.....\n.6...\n.....").unwrap();
+        let profile = SolverConfig::default_profile(grid.rules());
+        SolverConfig::profile(profile)
+            .unwrap()
+            .build()
+            .unwrap()
+            .solve(&mut grid)
+            .unwrap();
+        assert_eq!(
+            grid.to_input_string(),
+            "# variant: nuribou\nB1BW6\nWB2BW\nWBWBW\nW6BWW\nBWWBB"
+        );
+    }
+
+    #[test]
+    fn test_void_cells() {
+        // The corners and the center are not part of the board, so the wall can surround the hole
+        let mut grid = Grid::from_str("-1.4-\n.....\n.1-..\n.....\n-..3-").unwrap();
+        SolverConfig::profile("full")
+            .unwrap()
+            .build()
+            .unwrap()
+            .solve(&mut grid)
+            .unwrap();
+        assert_eq!(grid.to_input_string(), "-1B4-\nBBBWW\nB1-BW\nBBBBB\n-WW3-");
+    }
+
+    #[test]
+    fn test_wrap() {
+        // Unsolvable with hard edges, since the islands of the top-left 3 and the 2 cross them
+        let mut grid = Grid::from_str("# wrap: true\n3....\n..3..\n.....\n....2\n.1.1.").unwrap();
+        SolverConfig::profile("full")
+            .unwrap()
+            .build()
+            .unwrap()
+            .solve(&mut grid)
+            .unwrap();
+        assert_eq!(
+            grid.to_input_string(),
+            "# wrap: true\n3BWBB\nWB3BW\nBBWBB\nWBBB2\nB1B1B"
+        );
+    }
+
+    #[test]
+    fn test_hex() {
+        // Has a unique solution, checked by brute force
+        let mut grid = Grid::from_str(concat!(
+            "# layout: hex\n",
+            ". 1 . . 1\n",
+            " . . . . .\n",
+            ". . 4 . .\n",
+            " . . . 2 .\n",
+            ". 2 . . .",
+        ))
+        .unwrap();
+        SolverConfig::profile("full")
+            .unwrap()
+            .build()
+            .unwrap()
+            .solve(&mut grid)
+            .unwrap();
+        assert_eq!(
+            grid.to_input_string(),
+            concat!(
+                "# layout: hex\n",
+                "B 1 B B 1\n",
+                " B B W B B\n",
+                "W W 4 B W\n",
+                " B B B 2 B\n",
+                "B 2 W B B",
+            )
+        );
+    }
+
+    #[test]
+    fn test_invalid_board() {
+        // The first and last rows would be shifted the same way
+        assert!(Grid::from_str("# layout: hex\n# wrap: true\n. 1\n . .\n. .").is_err());
+        assert!(Grid::from_str("# layout: hex\n# wrap: true\n. 1\n . .").is_ok());
+        // The white cell touches the 1 across the edge
+        assert!(Grid::from_str("# wrap: true\n1.W").is_err());
+        assert!(Grid::from_str("1.W").is_ok());
+    }
+
+    #[test]
+    fn test_unsupported_rules() {
+        // Bounding boxes don't exist across the edges of a wrapping board
+        assert!(
+            Grid::from_str("# variant: nuribou\n# wrap: true\nB..B\n....\n....\n....").is_err()
+        );
+        assert!(Grid::from_str("# variant: mochikoro\n# wrap: true\nW.BW\n....").is_err());
+        assert!(Grid::from_str("# wrap: true\nB..B\n....\n....\n....").is_ok());
+        // Nor for hex cells
+        assert!(Grid::from_str("# variant: mochikoro\n# layout: hex\nW W\n W .").is_err());
+        assert!(Grid::from_str("# variant: nuribou\n# layout: hex\nB B\n . .").is_err());
+        assert!(Grid::from_str("# layout: hex\nW W\n W .").is_ok());
+    }
+
+    #[test]
+    #[should_panic(expected = "the mochikoro rules are not supported on this board")]
+    fn test_unsupported_variant() {
+        let options = BoardOptions {
+            wrap: true,
+            ..Default::default()
+        };
+        let _ = Grid::with_options(2, 4, options, []).with_variant(Variant::Mochikoro);
+    }
+
+    #[test]
+    fn test_progress() {
+        let events = Arc::new(Mutex::new(Vec::<Progress>::new()));
+        let reporter = {
+            let events = Arc::clone(&events);
+            move |progress: &Progress| events.lock().unwrap().push(progress.clone())
+        };
+        let context = SolveContext::default().with_progress(Arc::new(reporter), Duration::ZERO);
+
+        let mut grid = Grid::from_str(WIKIPEDIA).unwrap();
+        Solver::new(strategies())
+            .solve_with_context(&mut grid, NoopLogger, &context)
+            .unwrap();
+
+        let events = events.lock().unwrap();
+        assert!(events.iter().any(|progress| progress.depth > 0));
+        assert!(events
+            .windows(2)
+            .all(|pair| pair[0].decided_cells <= pair[1].decided_cells));
+
+        let last = events.last().unwrap();
+        assert_eq!(last.depth, 0);
+        assert_eq!(last.fraction_decided(), 1.0);
+        assert_eq!(last.hypotheses, context.hypotheses());
+        assert!(last.hypotheses > 0);
+    }
+}
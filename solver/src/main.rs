@@ -2,31 +2,45 @@ use std::fs;
 use std::str::FromStr;
 
 use nurikabe::{
-    strategy::{
-        avoid_pools::AvoidPools, complete_islands::CompleteIslands, confinement::Confinement,
-        dual_liberties::DualLiberties, hypotheticals::Hypotheticals,
-        single_liberties::SingleLiberties, unreachable_cells::UnreachableCells,
-    },
-    DisplayLogger, Grid, Solver,
+    config::{ConfigError, SolverConfig},
+    strategy::registry::StrategyRegistry,
+    DisplayLogger, Grid, Variant,
 };
 
 fn main() {
-    let solver = Solver::new(vec![
-        Box::new(CompleteIslands),
-        Box::new(SingleLiberties),
-        Box::new(DualLiberties),
-        Box::new(AvoidPools),
-        Box::new(UnreachableCells),
-        Box::new(Confinement),
-        Box::new(Hypotheticals::new(vec![
-            Box::new(CompleteIslands),
-            Box::new(SingleLiberties),
-            Box::new(DualLiberties),
-            Box::new(AvoidPools),
-            Box::new(UnreachableCells),
-            // Box::new(Confinement),
-        ])),
-    ]);
+    // Usage: nurikabe [--variant=<name>] <puzzle file> [<profile or config>]
+    //
+    // Pass a profile name (see `SolverConfig::PROFILES`) or, with the `config` feature, the path
+    // to a TOML config file to tune the solver. Defaults to the profile for the grid's variant,
+    // which is read from the puzzle header or given with `--variant=<name>`.
+    let mut puzzle_arg = None;
+    let mut config_arg = None;
+    let mut variant_arg = None;
+    for arg in std::env::args().skip(1) {
+        if arg == "--list-strategies" {
+            for info in StrategyRegistry::builtin().iter() {
+                println!("{:<18}{}", info.name(), info.description());
+            }
+            return;
+        } else if let Some(name) = arg.strip_prefix("--variant=") {
+            let Ok(variant) = Variant::from_str(name) else {
+                let names: Vec<_> = Variant::ALL.iter().map(|variant| variant.name()).collect();
+                exit_with_error(&format!(
+                    "unknown variant {:?}, expected one of: {}",
+                    name,
+                    names.join(", ")
+                ));
+            };
+            variant_arg = Some(variant);
+        } else if puzzle_arg.is_none() {
+            puzzle_arg = Some(arg);
+        } else {
+            config_arg = Some(arg);
+        }
+    }
+    let Some(puzzle_arg) = puzzle_arg else {
+        exit_with_error("usage: nurikabe [--variant=<name>] <puzzle file> [<profile or config>]");
+    };
 
     // https://www.puzzle-nurikabe.com/
     // 5x5 Nurikabe Hard Puzzle ID: 9,690,008
@@ -66,11 +80,50 @@ fn main() {
     // ))
     // .unwrap();
 
-    let input_string =
-        fs::read_to_string("data/puzzles/puzzle-nurikabe-com/15x15-hard/3079.txt").unwrap();
-    let mut grid = Grid::from_str(input_string.as_str()).unwrap();
+    // e.g. data/puzzles/puzzle-nurikabe-com/15x15-hard/3079.txt
+    let input_string = fs::read_to_string(&puzzle_arg)
+        .unwrap_or_else(|err| exit_with_error(&format!("cannot read {:?}: {}", puzzle_arg, err)));
+    let mut grid = Grid::from_str(input_string.as_str())
+        .unwrap_or_else(|()| exit_with_error(&format!("invalid puzzle in {:?}", puzzle_arg)));
+    if let Some(variant) = variant_arg {
+        if !grid.supports_rules(&variant) {
+            exit_with_error(&format!(
+                "the {} rules are not supported on this board",
+                variant.name()
+            ));
+        }
+        grid = grid.with_variant(variant);
+    }
+
+    let config_arg =
+        config_arg.unwrap_or_else(|| SolverConfig::default_profile(grid.rules()).to_string());
+    // Anything that doesn't look like a path is a profile name, so typos in it are reported as such
+    #[cfg(feature = "config")]
+    let config =
+        if config_arg.contains(['/', std::path::MAIN_SEPARATOR]) || config_arg.ends_with(".toml") {
+            SolverConfig::from_file(&config_arg)
+        } else {
+            SolverConfig::profile(&config_arg)
+        };
+    #[cfg(not(feature = "config"))]
+    let config = SolverConfig::profile(&config_arg);
+    let solver = config
+        .and_then(|config| config.build())
+        .unwrap_or_else(|err| match err {
+            ConfigError::UnknownProfile(_) => exit_with_error(&format!(
+                "{}, expected one of: {}",
+                err,
+                SolverConfig::PROFILES.join(", ")
+            )),
+            _ => exit_with_error(&err.to_string()),
+        });
 
     println!("{}", grid);
     let solver_result = solver.solve_with_logger(&mut grid, DisplayLogger::new());
     println!("{:?}", solver_result);
 }
+
+fn exit_with_error(message: &str) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(2);
+}
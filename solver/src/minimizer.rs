@@ -0,0 +1,201 @@
+//! Reduces the clues of existing puzzles, while they stay uniquely solvable with a chosen solver
+//! configuration.
+//!
+//! Every island needs exactly one clue, so a clue can only be removed by merging its island with
+//! neighboring ones: whitening a black cell between islands joins them into one island, whose clue
+//! is one of theirs with the combined size. This changes the solution, so every merge is checked
+//! by solving the resulting puzzle.
+
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+use crate::config::{ConfigError, SolverConfig};
+use crate::generator::{
+    build_checker, component, index, is_black_connected, solve_expected, Puzzle,
+};
+use crate::{Grid, Solver};
+
+/// Describes how a [`Minimizer`] reduces clues.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MinimizerConfig {
+    /// The solver the puzzle must stay solvable with. Solutions found by hypotheticals are only
+    /// adopted once they are proven to be unique.
+    pub solver: SolverConfig,
+    /// After merging islands, also replace numbers by `?` clues where the solver doesn't need the
+    /// size of the island.
+    pub allow_unknown_sizes: bool,
+}
+
+impl MinimizerConfig {
+    pub fn new(solver: SolverConfig) -> Self {
+        Self {
+            solver,
+            allow_unknown_sizes: false,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum MinimizerError {
+    Config(ConfigError),
+    /// The given grid isn't solved.
+    NotSolved,
+    /// The solver can't solve the puzzle from its clues to begin with.
+    Unsolvable,
+}
+
+impl Display for MinimizerError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MinimizerError::Config(err) => write!(f, "invalid solver config: {}", err),
+            MinimizerError::NotSolved => write!(f, "the grid is not solved"),
+            MinimizerError::Unsolvable => write!(f, "the solver can't solve the puzzle"),
+        }
+    }
+}
+
+impl Error for MinimizerError {}
+
+pub struct Minimizer {
+    solver: Solver,
+    allow_unknown_sizes: bool,
+}
+
+impl Minimizer {
+    pub fn new(config: MinimizerConfig) -> Result<Self, MinimizerError> {
+        Ok(Self {
+            solver: build_checker(&config.solver).map_err(MinimizerError::Config)?,
+            allow_unknown_sizes: config.allow_unknown_sizes,
+        })
+    }
+
+    /// Reduces the clues of a solved grid greedily, merging islands in row-major order of the
+    /// cells between them, and returns the puzzle with the fewest clues found.
+    pub fn minimize(&self, solved: &Grid) -> Result<Puzzle, MinimizerError> {
+        if !solved.is_complete() {
+            return Err(MinimizerError::NotSolved);
+        }
+
+        let mut white = vec![false; solved.num_rows * solved.num_cols];
+        for (coord, cell) in solved.iter() {
+            white[index(solved, coord)] = !cell.state.unwrap().is_black();
+        }
+        let mut puzzle =
+            solve_expected(&self.solver, solved.with_same_board(solved.clues()), &white)
+                .ok_or(MinimizerError::Unsolvable)?;
+
+        while let Some(merged) = self.merge_islands(&puzzle, &white) {
+            (puzzle, white) = merged;
+        }
+
+        if self.allow_unknown_sizes {
+            for (coord, _) in puzzle.clues.clues() {
+                let clues = puzzle
+                    .clues
+                    .clues()
+                    .into_iter()
+                    .map(|(clue, number)| (clue, number.filter(|_| clue != coord)));
+                let candidate = puzzle.clues.with_same_board(clues);
+                if let Some(candidate) = solve_expected(&self.solver, candidate, &white) {
+                    puzzle = candidate;
+                }
+            }
+        }
+
+        Ok(puzzle)
+    }
+
+    /// Tries to whiten a black cell next to several islands, keeping one of their clues. Returns
+    /// the first resulting puzzle the solver solves, along with its white cells.
+    fn merge_islands(&self, puzzle: &Puzzle, white: &[bool]) -> Option<(Puzzle, Vec<bool>)> {
+        let grid = &puzzle.solution;
+        let clues = puzzle.clues.clues();
+
+        for (coord, cell) in grid.iter() {
+            if !cell.state.is_some_and(|state| state.is_black()) {
+                continue;
+            }
+
+            let mut merged_white = white.to_vec();
+            merged_white[index(grid, coord)] = true;
+            let island = component(grid, &merged_white, coord, true);
+            let island_clues = clues
+                .iter()
+                .filter(|(clue, _)| island.contains(clue))
+                .collect::<Vec<_>>();
+            if island_clues.len() < 2 {
+                continue;
+            }
+            if grid.rules().is_wall_connected() && !is_black_connected(grid, &merged_white) {
+                continue;
+            }
+
+            for &&(kept, number) in &island_clues {
+                let merged_clues = clues
+                    .iter()
+                    .filter(|(clue, _)| !island.contains(clue))
+                    .copied()
+                    .chain([(kept, number.map(|_| island.len()))]);
+                let candidate = grid.with_same_board(merged_clues);
+                if let Some(candidate) = solve_expected(&self.solver, candidate, &merged_white) {
+                    return Some((candidate, merged_white));
+                }
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::{Minimizer, MinimizerConfig, MinimizerError};
+    use crate::config::SolverConfig;
+    use crate::Grid;
+
+    fn minimize(
+        solved: &str,
+        allow_unknown_sizes: bool,
+    ) -> Result<(String, String), MinimizerError> {
+        let mut config = MinimizerConfig::new(SolverConfig::profile("human-advanced").unwrap());
+        config.allow_unknown_sizes = allow_unknown_sizes;
+        let puzzle = Minimizer::new(config)?.minimize(&Grid::from_str(solved).unwrap())?;
+        Ok((
+            puzzle.clues.to_input_string(),
+            puzzle.solution.to_input_string(),
+        ))
+    }
+
+    #[test]
+    fn test_merge_islands() {
+        // The top 1 and 2 are merged into a 4 through the cell between them
+        assert_eq!(
+            minimize("BBB1B\nBW2BB\nBBB1B\nBW2BB\nBBBB1", false).unwrap(),
+            (
+                ".....\n..4..\n...1.\n..2..\n....1".to_string(),
+                "BBWWB\nBW4BB\nBBB1B\nBW2BB\nBBBB1".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_unknown_sizes() {
+        let (clues, _) = minimize("BBB1B\nBW2BB\nBBB1B\nBW2BB\nBBBB1", true).unwrap();
+        assert_eq!(clues, ".....\n..4..\n...1.\n..2..\n....?");
+    }
+
+    #[test]
+    fn test_unsolvable() {
+        // The island of the 3 could also bend down
+        assert!(matches!(
+            minimize("3WW\nBBB", false),
+            Err(MinimizerError::Unsolvable)
+        ));
+        assert!(matches!(
+            minimize("3..\n...", false),
+            Err(MinimizerError::NotSolved)
+        ));
+    }
+}
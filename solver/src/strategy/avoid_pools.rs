@@ -1,4 +1,4 @@
-use crate::{Coord, SolverError, State};
+use crate::{SolverError, State};
 
 use super::{MarkSet, Strategy, StrategyResult};
 
@@ -10,44 +10,48 @@ impl Strategy for AvoidPools {
     }
 
     fn apply(&self, grid: &mut crate::Grid) -> StrategyResult {
+        if grid.rules().allows_pools() {
+            return Ok(false);
+        }
+
         let mut mark_set = MarkSet::new();
 
-        for col in 1..grid.num_cols {
-            for row in 1..grid.num_rows {
-                let mut cells = [
-                    Coord::new(row - 1, col - 1),
-                    Coord::new(row - 1, col),
-                    Coord::new(row, col - 1),
-                    Coord::new(row, col),
-                ]
-                .map(|c| (c, grid.cell(c).state));
-
-                assert!(Some(State::Black) > None);
-                cells.sort_unstable_by_key(|(_, state)| *state);
-
-                match cells {
-                    // With three black cells in a 2x2 square, a remaining unknown cell must be
-                    // marked white.
-                    [(coord, None), (_, Some(State::Black)), (_, Some(State::Black)), (_, Some(State::Black))] =>
-                    {
-                        mark_set.insert(coord, State::White);
-                    }
-                    // With two black cells and two unknown cells in a 2x2 square. If marking one of
-                    // the unknown cells black would make the other one unreachable, then it must be
-                    // marked white.
-                    [(coord_1, None), (coord_2, None), (_, Some(State::Black)), (_, Some(State::Black))] => {
-                        if grid.is_cell_unreachable(coord_1, [coord_2]) {
-                            mark_set.insert(coord_2, State::White);
-                        } else if grid.is_cell_unreachable(coord_2, [coord_1]) {
-                            mark_set.insert(coord_1, State::White);
-                        }
-                    }
-                    [(_, Some(State::Black)), (_, Some(State::Black)), (_, Some(State::Black)), (_, Some(State::Black))] =>
-                    {
-                        // Found a 2x2 pool of black cells.
-                        return Err(SolverError::Contradiction);
+        let bit_grid = grid.bit_grid();
+        if bit_grid.has_pool() {
+            // Found a pool of black cells.
+            return Err(SolverError::Contradiction);
+        }
+
+        // With all but one cell of a pool black, the remaining unknown cell must be marked white.
+        for coord in bit_grid.pool_completions() {
+            mark_set.insert(coord, State::White);
+        }
+
+        if !grid.rules().white_regions_need_numbers() {
+            // Any unknown cell can become a white region of its own, so none are unreachable
+            return mark_set.apply(grid);
+        }
+
+        let reachability = bit_grid.reachability();
+        for window in grid.pool_windows() {
+            let mut cells = window
+                .iter()
+                .map(|&c| (c, grid.cell(c).state))
+                .collect::<Vec<_>>();
+
+            assert!(Some(State::Black) > None);
+            cells.sort_unstable_by_key(|(_, state)| *state);
+
+            // With two unknown cells and all other cells of a pool black. If marking one of the
+            // unknown cells black would make the other one unreachable, then it must be marked
+            // white.
+            if let [(coord_1, None), (coord_2, None), rest @ ..] = &cells[..] {
+                if rest.iter().all(|(_, state)| *state == Some(State::Black)) {
+                    if reachability.is_cell_unreachable(*coord_1, [*coord_2]) {
+                        mark_set.insert(*coord_2, State::White);
+                    } else if reachability.is_cell_unreachable(*coord_2, [*coord_1]) {
+                        mark_set.insert(*coord_1, State::White);
                     }
-                    _ => {}
                 }
             }
         }
@@ -71,4 +75,12 @@ mod tests {
     // Two black cells and two unknown cells in a 2x2 square. One of the unknown cells must not be
     // marked black, because it would make the other one unreachable (and therefore also black).
     test_strategy!(test_two_black_1, AvoidPools, "BBW\n..W", Some("BBW\n.WW"));
+
+    // On a wrapping board, 2x2 squares can cross the edges.
+    test_strategy!(
+        test_wrap,
+        AvoidPools,
+        "# wrap: true\nB..B\n.9..\n....\nB...",
+        Some("# wrap: true\nB..B\n.9..\n....\nB..W")
+    );
 }
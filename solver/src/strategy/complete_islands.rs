@@ -1,4 +1,5 @@
-use crate::Grid;
+use crate::grid::Region;
+use crate::{Coord, Grid, State};
 
 use super::{MarkSet, Strategy, StrategyResult};
 
@@ -13,13 +14,30 @@ impl Strategy for CompleteIslands {
         let mut mark_set = MarkSet::new();
 
         for region in grid.regions() {
-            if region.state.is_numbered() && !grid.is_region_incomplete(region) {
-                mark_set.mark_as_black.extend(region.unknowns.iter());
-            }
+            Self::check_region(grid, region, &mut mark_set);
         }
 
         mark_set.apply(grid)
     }
+
+    fn apply_incremental(&self, grid: &mut Grid, changed: &[Coord]) -> Option<StrategyResult> {
+        let mut mark_set = MarkSet::new();
+
+        for region in grid.regions_near(changed) {
+            Self::check_region(grid, region, &mut mark_set);
+        }
+
+        Some(mark_set.apply(grid))
+    }
+}
+
+impl CompleteIslands {
+    fn check_region(grid: &Grid, region: &Region, mark_set: &mut MarkSet) {
+        // An island with a `?` clue is never known to be complete
+        if matches!(region.state, State::Numbered(Some(_))) && !grid.is_region_incomplete(region) {
+            mark_set.mark_as_black.extend(region.unknowns.iter());
+        }
+    }
 }
 
 #[cfg(test)]
@@ -53,4 +71,6 @@ mod tests {
         ".B.\nB1B\n.B.",
         None
     );
+
+    test_strategy!(question_mark, CompleteIslands, "...\n?W.\n...", None);
 }
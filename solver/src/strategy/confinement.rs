@@ -24,17 +24,16 @@ impl Strategy for Confinement {
             })?;
 
         grid.regions_iter()
-            .filter(|(_, region)| matches!(region.state, State::Numbered(number) if region.len() < number))
+            .filter(|(_, region)| matches!(region.state, State::Numbered(Some(number)) if region.len() < number))
             .try_for_each(|(region_id, region)| {
                 region.unknowns.iter().try_for_each(|&coord| {
                     let mut assume_visited = vec![coord];
                     assume_visited.extend(grid.valid_unknown_neighbors(coord));
 
                     grid.valid_neighbors(coord)
-                        .map(|coord| grid.cell(coord))
-                        .filter(|cell| matches!(cell.state, Some(State::White)))
-                        .for_each(|cell| {
-                            let region = grid.region(cell.region.unwrap()).unwrap();
+                        .filter(|&coord| matches!(grid.cell(coord).state, Some(State::White)))
+                        .for_each(|coord| {
+                            let region = grid.region(grid.region_id(coord).unwrap()).unwrap();
                             assume_visited.extend(region.unknowns.iter().copied());
                         });
 
@@ -1,4 +1,5 @@
-use crate::{Grid, State};
+use crate::grid::Region;
+use crate::{Coord, Grid, State};
 
 use super::{MarkSet, Strategy, StrategyResult};
 
@@ -13,24 +14,40 @@ impl Strategy for DualLiberties {
         let mut mark_set = MarkSet::new();
 
         for region in grid.regions() {
-            if let State::Numbered(number) = region.state {
-                if region.len() + 1 == number && region.unknowns_len() == 2 {
-                    let adj1 = grid.valid_unknown_neighbors(region.unknowns[0]);
-                    let adj2 = grid
-                        .valid_unknown_neighbors(region.unknowns[1])
-                        .collect::<Vec<_>>();
-
-                    for coord in adj1 {
-                        if adj2.contains(&coord) {
-                            mark_set.insert(coord, State::Black);
-                            break;
-                        }
+            Self::check_region(grid, region, &mut mark_set);
+        }
+
+        mark_set.apply(grid)
+    }
+
+    fn apply_incremental(&self, grid: &mut Grid, changed: &[Coord]) -> Option<StrategyResult> {
+        let mut mark_set = MarkSet::new();
+
+        for region in grid.regions_near(changed) {
+            Self::check_region(grid, region, &mut mark_set);
+        }
+
+        Some(mark_set.apply(grid))
+    }
+}
+
+impl DualLiberties {
+    fn check_region(grid: &Grid, region: &Region, mark_set: &mut MarkSet) {
+        if let State::Numbered(Some(number)) = region.state {
+            if region.len() + 1 == number && region.unknowns_len() == 2 {
+                let &unknown1 = region.unknowns.first().unwrap();
+                let &unknown2 = region.unknowns.last().unwrap();
+                let adj1 = grid.valid_unknown_neighbors(unknown1);
+                let adj2 = grid.valid_unknown_neighbors(unknown2).collect::<Vec<_>>();
+
+                for coord in adj1 {
+                    if adj2.contains(&coord) {
+                        mark_set.insert(coord, State::Black);
+                        break;
                     }
                 }
             }
         }
-
-        mark_set.apply(grid)
     }
 }
 
@@ -1,16 +1,292 @@
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
 use crate::grid::State;
-use crate::strategy::{Strategy, StrategyResult};
-use crate::{Grid, Solver, SolverError};
+use crate::strategy::ordering::{CandidateOrdering, RowMajor};
+use crate::strategy::transposition::TranspositionTable;
+use crate::strategy::{MarkSet, Strategy, StrategyResult};
+use crate::{Coord, Grid, NoopLogger, SolveContext, Solver, SolverError};
+
+/// Settings shared by every level of a (nested) [`Hypotheticals`] strategy.
+#[derive(Clone, Debug)]
+pub struct HypotheticalsOptions {
+    /// Order in which unknown cells are tried.
+    pub ordering: Arc<dyn CandidateOrdering>,
+    /// Maximum number of cells to try per application, or `None` for no limit. This counts cells,
+    /// not hypotheses: both states of a cell are tried, which counts as a single trial, so a limit
+    /// of `n` allows up to `2 * n` hypotheses.
+    pub max_trials: Option<usize>,
+    /// If neither state of a cell leads to a contradiction, commit every cell that ends up with the
+    /// same state in both branches (i.e. forcing chains).
+    pub learn_from_both_branches: bool,
+    /// What to do when a hypothesis leads to a complete solution.
+    pub on_solution: SolutionPolicy,
+    /// Remember the outcome of propagating a hypothesis by the Zobrist hash of the grid, so a grid
+    /// that is reached along several branches is only solved once. Holds at most this many
+    /// outcomes per level, or `None` to disable the table.
+    pub transposition_table_capacity: Option<usize>,
+    /// Evaluate candidate cells on a thread pool. The first deduction in candidate order is still
+    /// the one that gets applied, so results don't depend on scheduling. Without the `parallel`
+    /// feature, candidates are always evaluated one after another.
+    pub parallel: bool,
+}
+
+impl Default for HypotheticalsOptions {
+    fn default() -> Self {
+        Self {
+            ordering: Arc::new(RowMajor),
+            max_trials: None,
+            learn_from_both_branches: false,
+            on_solution: SolutionPolicy::default(),
+            transposition_table_capacity: None,
+            parallel: false,
+        }
+    }
+}
+
+/// What a [`Hypotheticals`] strategy does when a hypothesis leads to a complete solution.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "config", derive(serde::Deserialize))]
+#[cfg_attr(feature = "config", serde(rename_all = "kebab-case"))]
+pub enum SolutionPolicy {
+    /// Only mark the hypothesized cell, assuming the puzzle has a unique solution.
+    #[default]
+    MarkCell,
+    /// Replace the grid with the solved branch, assuming the puzzle has a unique solution.
+    Adopt,
+    /// Replace the grid with the solved branch only if the opposite state of the hypothesized cell
+    /// leads to a contradiction, which proves that the solution is unique.
+    AdoptIfUnique,
+}
 
 pub struct Hypotheticals {
     solver: Solver,
+    depth: usize,
+    options: HypotheticalsOptions,
+    transposition_table: Option<TranspositionTable<Branch>>,
+}
+
+/// Outcome of propagating a single hypothesis, along with the cells it marked.
+#[derive(Clone)]
+enum Branch {
+    Solved(Vec<(Coord, State)>),
+    Contradiction,
+    Stuck(Vec<(Coord, State)>),
 }
 
 impl Hypotheticals {
+    /// Creates a level-1 `Hypotheticals` strategy, which propagates each hypothesis using only the
+    /// given strategies.
     pub fn new(strategies: Vec<Box<dyn Strategy>>) -> Self {
+        Self::with_options(strategies, HypotheticalsOptions::default())
+    }
+
+    /// Creates a level-1 `Hypotheticals` strategy with the given options.
+    pub fn with_options(strategies: Vec<Box<dyn Strategy>>, options: HypotheticalsOptions) -> Self {
+        Self::with_depth(strategies, 1, options)
+    }
+
+    fn with_depth(
+        strategies: Vec<Box<dyn Strategy>>,
+        depth: usize,
+        options: HypotheticalsOptions,
+    ) -> Self {
         Self {
             solver: Solver::new(strategies),
+            depth,
+            transposition_table: options
+                .transposition_table_capacity
+                .map(TranspositionTable::new),
+            options,
+        }
+    }
+
+    /// Creates a level-`depth` `Hypotheticals` strategy. A level-N hypothesis is propagated using
+    /// the strategies returned by `strategies`, followed by a level-(N-1) `Hypotheticals` strategy.
+    /// All levels share the same options.
+    pub fn nested(
+        depth: usize,
+        options: HypotheticalsOptions,
+        strategies: impl Fn() -> Vec<Box<dyn Strategy>>,
+    ) -> Self {
+        assert!(depth >= 1, "Hypotheticals must have a depth of at least 1");
+
+        let mut hypotheticals = Self::with_options(strategies(), options.clone());
+        for level in 2..=depth {
+            let mut level_strategies = strategies();
+            level_strategies.push(Box::new(hypotheticals));
+            hypotheticals = Self::with_depth(level_strategies, level, options.clone());
+        }
+        hypotheticals
+    }
+
+    /// Returns the nesting level of this strategy.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Returns how many hypotheses at this level were resolved using the transposition table, or
+    /// `None` if it is disabled.
+    pub fn transposition_table_hits(&self) -> Option<usize> {
+        self.transposition_table.as_ref().map(|table| table.hits())
+    }
+
+    /// Returns the minimum hypothetical depth needed to solve the grid using the given strategies,
+    /// or `None` if the grid can't be solved with a depth of at most `max_depth`. A depth of 0
+    /// means that the strategies alone suffice.
+    pub fn min_depth(
+        grid: &Grid,
+        max_depth: usize,
+        strategies: impl Fn() -> Vec<Box<dyn Strategy>>,
+    ) -> Option<usize> {
+        (0..=max_depth).find(|&depth| {
+            let mut level_strategies = strategies();
+            if depth > 0 {
+                level_strategies.push(Box::new(Self::nested(
+                    depth,
+                    HypotheticalsOptions::default(),
+                    &strategies,
+                )));
+            }
+            Solver::new(level_strategies)
+                .solve(&mut grid.clone())
+                .is_ok()
+        })
+    }
+
+    /// Propagates a hypothesis, and then rolls the grid back to how it was.
+    fn try_state(
+        &self,
+        grid: &mut Grid,
+        coord: Coord,
+        state: State,
+        context: &SolveContext,
+    ) -> Result<Branch, SolverError> {
+        context.count_hypothesis()?;
+
+        let checkpoint = grid.checkpoint();
+        let branch = match grid.mark_cell(coord, state) {
+            Ok(()) => match self.propagate(grid, context) {
+                Ok(Branch::Solved(marks)) => Ok(Branch::Solved(
+                    [(coord, state)].into_iter().chain(marks).collect(),
+                )),
+                Ok(Branch::Contradiction) => Ok(Branch::Contradiction),
+                Ok(Branch::Stuck(marks)) => Ok(Branch::Stuck(
+                    [(coord, state)].into_iter().chain(marks).collect(),
+                )),
+                Err(err) => Err(err),
+            },
+            Err(_) => Ok(Branch::Contradiction),
+        };
+        grid.rollback(checkpoint);
+        branch
+    }
+
+    /// Solves the grid as far as possible, and then rolls it back to how it was. The outcome only
+    /// depends on the grid, so it is looked up in the transposition table if there is one.
+    fn propagate(&self, grid: &mut Grid, context: &SolveContext) -> Result<Branch, SolverError> {
+        let hash = grid.zobrist_hash();
+        if let Some(branch) = self
+            .transposition_table
+            .as_ref()
+            .and_then(|table| table.get(hash))
+        {
+            return Ok(branch);
+        }
+
+        let checkpoint = grid.checkpoint();
+        let branch = match self
+            .solver
+            .solve_with_context(grid, NoopLogger, &context.nested())
+        {
+            Ok(_) => Ok(Branch::Solved(grid.marks_since(&checkpoint))),
+            Err(SolverError::Contradiction) => Ok(Branch::Contradiction),
+            Err(SolverError::NoStrategyApplies) => Ok(Branch::Stuck(grid.marks_since(&checkpoint))),
+            Err(SolverError::BudgetExceeded) => Err(SolverError::BudgetExceeded),
+        };
+        grid.rollback(checkpoint);
+
+        if let (Some(table), Ok(branch)) = (&self.transposition_table, &branch) {
+            table.insert(hash, branch.clone());
         }
+        branch
+    }
+
+    /// Tries both states for the given cell, and returns the cells that can be marked as a result.
+    fn try_cell(
+        &self,
+        grid: &mut Grid,
+        coord: Coord,
+        context: &SolveContext,
+    ) -> Result<Option<MarkSet>, SolverError> {
+        let mut branch_marks = Vec::with_capacity(2);
+        let mut found_solution = false;
+
+        for state in [State::Black, State::White] {
+            match self.try_state(grid, coord, state, context)? {
+                Branch::Solved(marks) => match self.options.on_solution {
+                    SolutionPolicy::MarkCell => {
+                        let mut mark_set = MarkSet::new();
+                        mark_set.insert(coord, state);
+                        return Ok(Some(mark_set));
+                    }
+                    SolutionPolicy::Adopt => {
+                        return Ok(Some(Self::agreed_marks(&[marks])));
+                    }
+                    SolutionPolicy::AdoptIfUnique => {
+                        found_solution = true;
+                        branch_marks.push(marks);
+                    }
+                },
+                Branch::Contradiction if found_solution => {
+                    // The other state led to a solution, which is now known to be unique
+                    return Ok(Some(Self::agreed_marks(&branch_marks)));
+                }
+                Branch::Contradiction => {
+                    let mut mark_set = MarkSet::new();
+                    mark_set.insert(coord, state.opposite());
+                    return Ok(Some(mark_set));
+                }
+                Branch::Stuck(marks) => branch_marks.push(marks),
+            }
+        }
+
+        if !self.options.learn_from_both_branches {
+            return Ok(None);
+        }
+
+        // Neither branch led to a contradiction, but any cell they agree on must have that state
+        let mark_set = Self::agreed_marks(&branch_marks);
+        if mark_set.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(mark_set))
+        }
+    }
+
+    /// Returns the cells that were marked with the same state in all branches.
+    fn agreed_marks(branch_marks: &[Vec<(Coord, State)>]) -> MarkSet {
+        let mut mark_set = MarkSet::new();
+
+        let (first_marks, other_marks) = branch_marks.split_first().unwrap();
+        let other_marks = other_marks
+            .iter()
+            .map(|marks| marks.iter().copied().collect::<HashMap<_, _>>())
+            .collect::<Vec<_>>();
+
+        for &(coord, state) in first_marks {
+            if other_marks
+                .iter()
+                .all(|marks| marks.get(&coord) == Some(&state))
+            {
+                mark_set.insert(coord, state);
+            }
+        }
+
+        mark_set
     }
 }
 
@@ -20,39 +296,259 @@ impl Strategy for Hypotheticals {
     }
 
     fn apply(&self, grid: &mut Grid) -> StrategyResult {
-        let result = grid
+        self.apply_with_context(grid, &SolveContext::default())
+    }
+
+    fn apply_with_context(&self, grid: &mut Grid, context: &SolveContext) -> StrategyResult {
+        let mut candidates = grid
             .iter()
             .filter(|(_, cell)| cell.state.is_none())
-            .flat_map(|(coord, _)| {
-                [State::Black, State::White]
-                    .iter()
-                    .map(move |state| (coord, *state))
-            })
-            .find_map(|(coord, state)| {
-                let mut hypothetical_grid = grid.clone();
-                let result = hypothetical_grid
-                    .mark_cell(coord, state)
-                    .and_then(|_| self.solver.solve(&mut hypothetical_grid));
-                match result {
-                    Ok(_) => {
-                        // Solution found
-                        // TODO: Return the solution?
-                        Some((coord, state))
-                    }
-                    Err(SolverError::Contradiction) => {
-                        // Contradiction found
-                        Some((coord, state.opposite()))
-                    }
-                    Err(SolverError::NoStrategyApplies) => None,
-                }
-            });
+            .map(|(coord, _)| coord)
+            .collect::<Vec<_>>();
+        self.options.ordering.order(grid, &mut candidates);
+        candidates.truncate(self.options.max_trials.unwrap_or(usize::MAX));
 
-        match result {
-            Some((coord, state)) => {
-                grid.mark_cell(coord, state).unwrap();
-                Ok(true)
-            }
+        #[cfg(feature = "parallel")]
+        let result = if self.options.parallel {
+            // Every worker speculates on its own copy of the grid
+            let shared_grid: &Grid = grid;
+            candidates
+                .into_par_iter()
+                .map_init(
+                    || shared_grid.clone(),
+                    |worker_grid, coord| self.try_cell(worker_grid, coord, context),
+                )
+                .find_map_first(Result::transpose)
+        } else {
+            candidates
+                .into_iter()
+                .find_map(|coord| self.try_cell(grid, coord, context).transpose())
+        };
+        #[cfg(not(feature = "parallel"))]
+        let result = candidates
+            .into_iter()
+            .find_map(|coord| self.try_cell(grid, coord, context).transpose());
+
+        match result.transpose()? {
+            Some(mark_set) => mark_set.apply(grid),
             None => Ok(false),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::{Hypotheticals, HypotheticalsOptions, SolutionPolicy};
+    use crate::strategy::avoid_pools::AvoidPools;
+    use crate::strategy::complete_islands::CompleteIslands;
+    use crate::strategy::dual_liberties::DualLiberties;
+    use crate::strategy::single_liberties::SingleLiberties;
+    use crate::strategy::unreachable_cells::UnreachableCells;
+    use crate::strategy::{test_strategy, Strategy};
+    use crate::Grid;
+
+    fn basic_strategies() -> Vec<Box<dyn Strategy>> {
+        vec![
+            Box::new(CompleteIslands),
+            Box::new(SingleLiberties),
+            Box::new(DualLiberties),
+            Box::new(AvoidPools),
+            Box::new(UnreachableCells),
+        ]
+    }
+
+    fn learning_hypotheticals(learn_from_both_branches: bool) -> Hypotheticals {
+        Hypotheticals::with_options(
+            basic_strategies(),
+            HypotheticalsOptions {
+                learn_from_both_branches,
+                ..Default::default()
+            },
+        )
+    }
+
+    fn solving_hypotheticals(on_solution: SolutionPolicy) -> Hypotheticals {
+        Hypotheticals::with_options(
+            basic_strategies(),
+            HypotheticalsOptions {
+                on_solution,
+                ..Default::default()
+            },
+        )
+    }
+
+    // https://en.wikipedia.org/wiki/Nurikabe_(puzzle)
+    const WIKIPEDIA: &str = concat!(
+        "2........2\n",
+        "......2...\n",
+        ".2..7.....\n",
+        "..........\n",
+        "......3.3.\n",
+        "..2....3..\n",
+        "2..4......\n",
+        "..........\n",
+        ".1....2.4.",
+    );
+
+    #[test]
+    fn test_nested_depth() {
+        assert_eq!(Hypotheticals::new(basic_strategies()).depth(), 1);
+        assert_eq!(
+            Hypotheticals::nested(3, HypotheticalsOptions::default(), basic_strategies).depth(),
+            3
+        );
+    }
+
+    #[test]
+    fn test_min_depth() {
+        let grid = Grid::from_str("2..1.\n.....\n...3.\n....5\n.....").unwrap();
+        assert_eq!(
+            Hypotheticals::min_depth(&grid, 2, basic_strategies),
+            Some(0)
+        );
+
+        let grid = Grid::from_str(WIKIPEDIA).unwrap();
+        assert_eq!(Hypotheticals::min_depth(&grid, 1, basic_strategies), None);
+        assert_eq!(
+            Hypotheticals::min_depth(&grid, 2, basic_strategies),
+            Some(2)
+        );
+    }
+
+    #[test]
+    fn test_max_trials() {
+        let mut grid = Grid::from_str(WIKIPEDIA).unwrap();
+        let hypotheticals = Hypotheticals::with_options(
+            basic_strategies(),
+            HypotheticalsOptions {
+                max_trials: Some(0),
+                ..Default::default()
+            },
+        );
+        assert_eq!(hypotheticals.apply(&mut grid), Ok(false));
+    }
+
+    // No single hypothesis leads to a contradiction, but both branches of a hypothesis agree that
+    // the bottom-left cell is black.
+    test_strategy!(
+        test_single_branch,
+        learning_hypotheticals(false),
+        ".3..\n....\n...4\n....",
+        None::<&str>
+    );
+    test_strategy!(
+        test_learn_from_both_branches,
+        learning_hypotheticals(true),
+        ".3..\n....\n...4\n....",
+        Some(".3..\n....\n...4\nB...")
+    );
+
+    // A hypothesis on the top-left cell solves the grid, and the other state leads to a
+    // contradiction.
+    test_strategy!(
+        test_solution_mark_cell,
+        solving_hypotheticals(SolutionPolicy::MarkCell),
+        "..2.\n....\n3..2",
+        Some("W.2.\n....\n3..2")
+    );
+    test_strategy!(
+        test_solution_adopt,
+        solving_hypotheticals(SolutionPolicy::Adopt),
+        "..2.\n....\n3..2",
+        Some("WB2W\nWBBB\n3BW2")
+    );
+    test_strategy!(
+        test_solution_adopt_if_unique,
+        solving_hypotheticals(SolutionPolicy::AdoptIfUnique),
+        "..2.\n....\n3..2",
+        Some("WB2W\nWBBB\n3BW2")
+    );
+
+    // This grid has two solutions. `Adopt` takes the first one it finds, while `AdoptIfUnique` only
+    // marks a cell whose other state leads to a contradiction.
+    test_strategy!(
+        test_solution_not_unique_adopt,
+        solving_hypotheticals(SolutionPolicy::Adopt),
+        "3...\n....\n..2.",
+        Some("3BBB\nWBWB\nWB2B")
+    );
+    test_strategy!(
+        test_solution_not_unique_adopt_if_unique,
+        solving_hypotheticals(SolutionPolicy::AdoptIfUnique),
+        "3...\n....\n..2.",
+        Some("3..B\n....\n..2.")
+    );
+
+    #[test]
+    fn test_transposition_table() {
+        let hypotheticals = Hypotheticals::with_options(
+            basic_strategies(),
+            HypotheticalsOptions {
+                transposition_table_capacity: Some(1000),
+                ..Default::default()
+            },
+        );
+        let grid = Grid::from_str(WIKIPEDIA).unwrap();
+
+        let mut first_grid = grid.clone();
+        hypotheticals.apply(&mut first_grid).unwrap();
+        assert_eq!(hypotheticals.transposition_table_hits(), Some(0));
+
+        // The same hypotheses are reached again, so they don't need to be solved again
+        let mut second_grid = grid.clone();
+        hypotheticals.apply(&mut second_grid).unwrap();
+        assert!(hypotheticals.transposition_table_hits().unwrap() > 0);
+        assert_eq!(first_grid.to_input_string(), second_grid.to_input_string());
+    }
+
+    #[test]
+    fn test_transposition_table_across_boards() {
+        // The same cells are marked in both grids, but the second one has a void cell
+        let hypotheticals = Hypotheticals::with_options(
+            basic_strategies(),
+            HypotheticalsOptions {
+                on_solution: SolutionPolicy::Adopt,
+                transposition_table_capacity: Some(100),
+                ..Default::default()
+            },
+        );
+        let mut grid = Grid::from_str("1...").unwrap();
+        assert_eq!(hypotheticals.apply(&mut grid), Ok(true));
+        assert_eq!(grid.to_input_string(), "1BBB");
+
+        let mut grid = Grid::from_str("1..-").unwrap();
+        assert_eq!(hypotheticals.apply(&mut grid), Ok(true));
+        assert_eq!(grid.to_input_string(), "1BB-");
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_parallel() {
+        let hypotheticals = |parallel| {
+            let options = HypotheticalsOptions {
+                on_solution: SolutionPolicy::MarkCell,
+                parallel,
+                ..Default::default()
+            };
+            Hypotheticals::nested(2, options, basic_strategies)
+        };
+        let (parallel, sequential) = (hypotheticals(true), hypotheticals(false));
+        let basic_solver = crate::Solver::new(basic_strategies());
+
+        let mut grid = Grid::from_str(WIKIPEDIA).unwrap();
+        assert!(basic_solver.solve(&mut grid).is_err());
+
+        // Every application must make the same deduction, not just lead to the same solution
+        while !grid.is_complete() {
+            let mut parallel_grid = grid.clone();
+            let result = parallel.apply(&mut parallel_grid);
+            assert_eq!(result, sequential.apply(&mut grid));
+            assert_eq!(result, Ok(true));
+            assert_eq!(parallel_grid.to_input_string(), grid.to_input_string());
+
+            let _ = basic_solver.solve(&mut grid);
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{Coord, Grid, SolverError};
+use crate::{Coord, Grid, SolveContext, SolverError};
 use std::collections::HashSet;
 
 pub mod avoid_pools;
@@ -6,14 +6,36 @@ pub mod complete_islands;
 pub mod confinement;
 pub mod dual_liberties;
 pub mod hypotheticals;
+pub mod ordering;
+pub mod rectangles;
+pub mod registry;
 pub mod single_liberties;
+pub mod strips;
+mod transposition;
 pub mod unreachable_cells;
 
 pub type StrategyResult = Result<bool, SolverError>;
 
-pub trait Strategy {
+/// A deduction technique. Strategies must only depend on the state of the grid, so the solver can
+/// skip a strategy when no cells changed since it last found nothing to mark.
+pub trait Strategy: Send + Sync {
     fn name(&self) -> &str;
     fn apply(&self, grid: &mut Grid) -> StrategyResult;
+
+    /// Applies the strategy, given the cells that were marked since it was last applied to this
+    /// grid. Only the parts of the grid affected by those cells need to be checked, but the result
+    /// must be the same as that of [`Strategy::apply`].
+    ///
+    /// Returns `None` (without changing the grid) if the strategy can't be applied incrementally.
+    fn apply_incremental(&self, _grid: &mut Grid, _changed: &[Coord]) -> Option<StrategyResult> {
+        None
+    }
+
+    /// Applies the strategy as part of a solve. Strategies that solve nested grids should pass the
+    /// context on, so the solve's budget covers them too.
+    fn apply_with_context(&self, grid: &mut Grid, _context: &SolveContext) -> StrategyResult {
+        self.apply(grid)
+    }
 }
 
 struct MarkSet {
@@ -37,8 +59,12 @@ impl MarkSet {
         .insert(coord)
     }
 
+    fn is_empty(&self) -> bool {
+        self.mark_as_white.is_empty() && self.mark_as_black.is_empty()
+    }
+
     fn apply(self, grid: &mut Grid) -> StrategyResult {
-        let result = !self.mark_as_white.is_empty() || !self.mark_as_black.is_empty();
+        let result = !self.is_empty();
         for coord in self.mark_as_black {
             grid.mark_cell(coord, State::Black)?;
         }
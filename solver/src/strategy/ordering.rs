@@ -0,0 +1,192 @@
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+
+use crate::grid::State;
+use crate::{Coord, Grid};
+
+/// Decides in which order [`Hypotheticals`](super::hypotheticals::Hypotheticals) tries unknown
+/// cells. Since the first deduction found is the one that gets applied, a good ordering finds
+/// deductions after fewer sub-solves.
+pub trait CandidateOrdering: Send + Sync {
+    fn name(&self) -> &str;
+    /// Sorts the candidate cells, most promising first. Candidates are given in row-major order.
+    fn order(&self, grid: &Grid, candidates: &mut [Coord]);
+}
+
+impl Debug for dyn CandidateOrdering {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Returns all built-in orderings.
+pub fn builtin_orderings() -> Vec<Arc<dyn CandidateOrdering>> {
+    vec![
+        Arc::new(RowMajor),
+        Arc::new(NearlyCompleteIslands),
+        Arc::new(PoolWindows),
+        Arc::new(FewestOwners),
+        Arc::new(MostConstrained),
+    ]
+}
+
+/// Tries cells in row-major order.
+pub struct RowMajor;
+
+impl CandidateOrdering for RowMajor {
+    fn name(&self) -> &str {
+        "RowMajor"
+    }
+
+    fn order(&self, _grid: &Grid, _candidates: &mut [Coord]) {}
+}
+
+/// Tries cells next to numbered islands first, starting with the islands that are missing the
+/// fewest cells.
+pub struct NearlyCompleteIslands;
+
+impl CandidateOrdering for NearlyCompleteIslands {
+    fn name(&self) -> &str {
+        "NearlyCompleteIslands"
+    }
+
+    fn order(&self, grid: &Grid, candidates: &mut [Coord]) {
+        candidates.sort_by_cached_key(|&coord| {
+            grid.valid_neighbors(coord)
+                .filter_map(|adj_coord| grid.region_id(adj_coord))
+                .filter_map(|region_id| {
+                    let region = grid.region(region_id).unwrap();
+                    match region.state {
+                        State::Numbered(number) => {
+                            Some(grid.max_island_len(number).saturating_sub(region.len()))
+                        }
+                        _ => None,
+                    }
+                })
+                .min()
+                .unwrap_or(usize::MAX)
+        });
+    }
+}
+
+/// Tries cells in pools (e.g. 2x2 windows) that are two black cells short of complete first, since
+/// marking those black usually forces the rest of the pool.
+pub struct PoolWindows;
+
+impl CandidateOrdering for PoolWindows {
+    fn name(&self) -> &str {
+        "PoolWindows"
+    }
+
+    fn order(&self, grid: &Grid, candidates: &mut [Coord]) {
+        let mut pool_counts = vec![0usize; grid.num_rows * grid.num_cols];
+
+        for window in grid.pool_windows() {
+            let num_black = window
+                .iter()
+                .filter(|&&coord| grid.cell(coord).state == Some(State::Black))
+                .count();
+            if num_black + 2 == window.len() {
+                for coord in window {
+                    pool_counts[grid.coord_to_index(*coord)] += 1;
+                }
+            }
+        }
+
+        candidates.sort_by_key(|coord| std::cmp::Reverse(pool_counts[grid.coord_to_index(*coord)]));
+    }
+}
+
+/// Tries cells that the fewest numbered islands could still reach first. Cells that no island can
+/// reach come last, as they are black anyway and marking them rarely leads anywhere.
+pub struct FewestOwners;
+
+impl CandidateOrdering for FewestOwners {
+    fn name(&self) -> &str {
+        "FewestOwners"
+    }
+
+    fn order(&self, grid: &Grid, candidates: &mut [Coord]) {
+        let owner_counts = grid.possible_owner_counts();
+        candidates.sort_by_key(|&coord| {
+            let count = owner_counts[grid.coord_to_index(coord)];
+            (count == 0, count)
+        });
+    }
+}
+
+/// Tries cells with the fewest unknown neighbors first.
+pub struct MostConstrained;
+
+impl CandidateOrdering for MostConstrained {
+    fn name(&self) -> &str {
+        "MostConstrained"
+    }
+
+    fn order(&self, grid: &Grid, candidates: &mut [Coord]) {
+        candidates.sort_by_cached_key(|&coord| grid.valid_unknown_neighbors(coord).count());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::{
+        CandidateOrdering, FewestOwners, MostConstrained, NearlyCompleteIslands, PoolWindows,
+    };
+    use crate::{Coord, Grid};
+
+    fn ordered(ordering: impl CandidateOrdering, input: &str) -> Vec<Coord> {
+        let grid = Grid::from_str(input).unwrap();
+        let mut candidates = grid
+            .iter()
+            .filter(|(_, cell)| cell.state.is_none())
+            .map(|(coord, _)| coord)
+            .collect::<Vec<_>>();
+        ordering.order(&grid, &mut candidates);
+        candidates
+    }
+
+    #[test]
+    fn test_nearly_complete_islands() {
+        let candidates = ordered(NearlyCompleteIslands, "...\n...\n3.2");
+        assert_eq!(candidates[..2], [Coord::new(1, 2), Coord::new(2, 1)]);
+    }
+
+    #[test]
+    fn test_pool_windows() {
+        let candidates = ordered(PoolWindows, "...\n.BB\n...");
+        assert_eq!(
+            candidates[..4],
+            [
+                Coord::new(0, 1),
+                Coord::new(0, 2),
+                Coord::new(2, 1),
+                Coord::new(2, 2)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fewest_owners() {
+        let candidates = ordered(FewestOwners, "2...\n....\n....\n...2");
+        assert_eq!(
+            candidates[..4],
+            [
+                Coord::new(0, 1),
+                Coord::new(1, 0),
+                Coord::new(2, 3),
+                Coord::new(3, 2)
+            ]
+        );
+        // Cells that neither island can reach come last
+        assert_eq!(candidates.last(), Some(&Coord::new(3, 1)));
+    }
+
+    #[test]
+    fn test_most_constrained() {
+        let candidates = ordered(MostConstrained, "...\n.B.\n..W");
+        assert_eq!(candidates[..2], [Coord::new(1, 2), Coord::new(2, 1)]);
+    }
+}
@@ -0,0 +1,67 @@
+use crate::{Grid, State};
+
+use super::{MarkSet, Strategy, StrategyResult};
+
+/// For variants with rectangular islands (e.g. Mochikoro): fills in the bounding rectangle of
+/// every white region, and marks neighboring cells black that would stop it from being a
+/// rectangle.
+pub struct Rectangles;
+
+impl Strategy for Rectangles {
+    fn name(&self) -> &str {
+        "Rectangles"
+    }
+
+    fn apply(&self, grid: &mut Grid) -> StrategyResult {
+        if !grid.rules().has_rectangular_islands() {
+            return Ok(false);
+        }
+
+        let mut mark_set = MarkSet::new();
+
+        for region in grid.regions().filter(|region| !region.state.is_black()) {
+            let bounds = grid.region_bounds(region);
+
+            for coord in bounds.coords() {
+                if grid.cell(coord).state.is_none() {
+                    mark_set.insert(coord, State::White);
+                }
+            }
+
+            for &coord in &region.unknowns {
+                if !bounds.contains(coord) && !grid.is_rectangle_possible(bounds.extend(coord)) {
+                    mark_set.insert(coord, State::Black);
+                }
+            }
+        }
+
+        mark_set.apply(grid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::strategy::test_strategy;
+
+    use super::Rectangles;
+
+    test_strategy!(
+        test_fill_bounds,
+        Rectangles,
+        "# variant: mochikoro\nWW.\nW..",
+        Some("# variant: mochikoro\nWW.\nWW.")
+    );
+    test_strategy!(
+        test_complete_number,
+        Rectangles,
+        "# variant: mochikoro\n2W.\n...",
+        Some("# variant: mochikoro\n2WB\nBB.")
+    );
+    test_strategy!(
+        test_avoid_black,
+        Rectangles,
+        "# variant: mochikoro\nWW\n.B",
+        Some("# variant: mochikoro\nWW\nBB")
+    );
+    test_strategy!(test_nurikabe, Rectangles, "WW\nW.", None);
+}
@@ -0,0 +1,239 @@
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+
+use crate::strategy::avoid_pools::AvoidPools;
+use crate::strategy::complete_islands::CompleteIslands;
+use crate::strategy::confinement::Confinement;
+use crate::strategy::dual_liberties::DualLiberties;
+use crate::strategy::hypotheticals::Hypotheticals;
+use crate::strategy::rectangles::Rectangles;
+use crate::strategy::single_liberties::SingleLiberties;
+use crate::strategy::strips::Strips;
+use crate::strategy::unreachable_cells::UnreachableCells;
+use crate::strategy::Strategy;
+
+type Constructor = Arc<dyn Fn() -> Box<dyn Strategy> + Send + Sync>;
+
+/// A strategy that can be created by name.
+#[derive(Clone)]
+pub struct StrategyInfo {
+    name: String,
+    description: String,
+    constructor: Constructor,
+    /// Whether this is the built-in `Hypotheticals` strategy, which a
+    /// [`SolverConfig`](crate::config::SolverConfig) builds from its own settings instead
+    is_builtin_hypotheticals: bool,
+}
+
+impl StrategyInfo {
+    /// Returns the name of the strategy, which matches [`Strategy::name`].
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns a one-line, human-readable description of the strategy.
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    pub fn create(&self) -> Box<dyn Strategy> {
+        (self.constructor)()
+    }
+
+    pub(crate) fn is_builtin_hypotheticals(&self) -> bool {
+        self.is_builtin_hypotheticals
+    }
+}
+
+impl Debug for StrategyInfo {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StrategyInfo")
+            .field("name", &self.name)
+            .field("description", &self.description)
+            .finish()
+    }
+}
+
+/// Maps strategy names to constructors, so CLIs and config files can refer to strategies by name.
+/// Strategies from other crates can be added with [`StrategyRegistry::register`].
+#[derive(Clone, Debug, Default)]
+pub struct StrategyRegistry {
+    /// In order of registration
+    strategies: Vec<StrategyInfo>,
+}
+
+impl StrategyRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a registry containing all built-in strategies, from cheapest to most expensive.
+    pub fn builtin() -> Self {
+        let mut registry = Self::new();
+        registry.register(
+            "CompleteIslands",
+            "Surrounds islands that have reached their size with black cells.",
+            || Box::new(CompleteIslands),
+        );
+        registry.register(
+            "SingleLiberties",
+            "Extends regions that can only grow in one direction.",
+            || Box::new(SingleLiberties),
+        );
+        registry.register(
+            "DualLiberties",
+            "Marks cells black that neighbor both liberties of an island one cell short.",
+            || Box::new(DualLiberties),
+        );
+        registry.register(
+            "AvoidPools",
+            "Marks cells white to prevent 2x2 pools of black cells.",
+            || Box::new(AvoidPools),
+        );
+        registry.register(
+            "Rectangles",
+            "Keeps white regions rectangular, in variants that require it.",
+            || Box::new(Rectangles),
+        );
+        registry.register(
+            "Strips",
+            "Keeps black regions straight, in variants that require it.",
+            || Box::new(Strips),
+        );
+        registry.register(
+            "UnreachableCells",
+            "Marks cells black that no island can reach.",
+            || Box::new(UnreachableCells),
+        );
+        registry.register(
+            "Confinement",
+            "Marks cells that would stop a region from growing to its required size.",
+            || Box::new(Confinement),
+        );
+        registry.register_hypotheticals(|| {
+            Box::new(Hypotheticals::new(vec![
+                Box::new(CompleteIslands),
+                Box::new(SingleLiberties),
+                Box::new(DualLiberties),
+                Box::new(AvoidPools),
+                Box::new(UnreachableCells),
+            ]))
+        });
+        registry
+    }
+
+    /// Adds a strategy to the registry. The constructor must return strategies whose
+    /// [`Strategy::name`] is `name`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a strategy with the same name is already registered.
+    pub fn register(
+        &mut self,
+        name: impl Into<String>,
+        description: impl Into<String>,
+        constructor: impl Fn() -> Box<dyn Strategy> + Send + Sync + 'static,
+    ) {
+        self.insert(
+            name.into(),
+            description.into(),
+            Arc::new(constructor),
+            false,
+        );
+    }
+
+    /// Adds the built-in `Hypotheticals` strategy, which a
+    /// [`SolverConfig`](crate::config::SolverConfig) builds from its own settings instead.
+    fn register_hypotheticals(
+        &mut self,
+        constructor: impl Fn() -> Box<dyn Strategy> + Send + Sync + 'static,
+    ) {
+        self.insert(
+            "Hypotheticals".to_string(),
+            "Rules out states of unknown cells that lead to a contradiction.".to_string(),
+            Arc::new(constructor),
+            true,
+        );
+    }
+
+    fn insert(
+        &mut self,
+        name: String,
+        description: String,
+        constructor: Constructor,
+        is_builtin_hypotheticals: bool,
+    ) {
+        assert!(
+            self.get(&name).is_none(),
+            "strategy {:?} is already registered",
+            name
+        );
+        self.strategies.push(StrategyInfo {
+            name,
+            description,
+            constructor,
+            is_builtin_hypotheticals,
+        });
+    }
+
+    pub fn get(&self, name: &str) -> Option<&StrategyInfo> {
+        self.strategies.iter().find(|info| info.name == name)
+    }
+
+    /// Creates the strategy with the given name, or returns `None` if it isn't registered.
+    pub fn create(&self, name: &str) -> Option<Box<dyn Strategy>> {
+        self.get(name).map(StrategyInfo::create)
+    }
+
+    /// Returns all registered strategies, in order of registration.
+    pub fn iter(&self) -> impl Iterator<Item = &StrategyInfo> {
+        self.strategies.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StrategyRegistry;
+    use crate::strategy::{Strategy, StrategyResult};
+    use crate::Grid;
+
+    struct DoNothing;
+
+    impl Strategy for DoNothing {
+        fn name(&self) -> &str {
+            "DoNothing"
+        }
+
+        fn apply(&self, _grid: &mut Grid) -> StrategyResult {
+            Ok(false)
+        }
+    }
+
+    #[test]
+    fn test_builtin_names() {
+        let registry = StrategyRegistry::builtin();
+        for info in registry.iter() {
+            assert_eq!(info.create().name(), info.name());
+        }
+        assert!(registry.create("DoNothing").is_none());
+    }
+
+    #[test]
+    fn test_register() {
+        let mut registry = StrategyRegistry::builtin();
+        registry.register("DoNothing", "Never marks any cells.", || {
+            Box::new(DoNothing)
+        });
+
+        assert_eq!(registry.iter().last().unwrap().name(), "DoNothing");
+        assert_eq!(registry.create("DoNothing").unwrap().name(), "DoNothing");
+    }
+
+    #[test]
+    #[should_panic(expected = "strategy \"AvoidPools\" is already registered")]
+    fn test_register_duplicate() {
+        let mut registry = StrategyRegistry::builtin();
+        registry.register("AvoidPools", "", || Box::new(DoNothing));
+    }
+}
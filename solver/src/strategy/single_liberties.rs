@@ -1,4 +1,5 @@
-use crate::Grid;
+use crate::grid::Region;
+use crate::{Coord, Grid};
 
 use super::{MarkSet, Strategy, StrategyResult};
 
@@ -13,18 +14,43 @@ impl Strategy for SingleLiberties {
         let mut mark_set = MarkSet::new();
 
         for region in grid.regions() {
-            if grid.is_region_incomplete(region) && region.unknowns_len() == 1 {
-                mark_set.insert(region.unknowns[0], region.state);
-            }
+            Self::check_region(grid, region, &mut mark_set);
         }
 
         mark_set.apply(grid)
     }
+
+    fn apply_incremental(&self, grid: &mut Grid, changed: &[Coord]) -> Option<StrategyResult> {
+        // Black regions away from the changed cells may have become incomplete
+        if grid.is_black_region_len_unknown() {
+            return None;
+        }
+
+        let mut mark_set = MarkSet::new();
+
+        for region in grid.regions_near(changed) {
+            Self::check_region(grid, region, &mut mark_set);
+        }
+
+        Some(mark_set.apply(grid))
+    }
+}
+
+impl SingleLiberties {
+    fn check_region(grid: &Grid, region: &Region, mark_set: &mut MarkSet) {
+        if grid.is_region_incomplete(region) && region.unknowns_len() == 1 {
+            let &coord = region.unknowns.first().unwrap();
+            mark_set.insert(coord, region.state);
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::str::FromStr;
+
     use crate::strategy::test_strategy;
+    use crate::{Coord, Grid, Strategy};
 
     use super::SingleLiberties;
 
@@ -43,4 +69,28 @@ mod tests {
         "4.\n.W\nBB",
         None
     );
+    test_strategy!(test_question_mark, SingleLiberties, "?..\nBBB", None);
+    test_strategy!(
+        test_black_question_mark,
+        SingleLiberties,
+        "B.?\nWW.\n..B",
+        Some("BB?\nWW.\n..B")
+    );
+
+    #[test]
+    fn test_incremental_question_mark() {
+        // Marking the bottom right cell makes the top left region incomplete
+        let grid = Grid::from_str("B.?\nW..\n..B").unwrap();
+
+        let mut full = grid.clone();
+        assert_eq!(SingleLiberties.apply(&mut full), Ok(true));
+        assert_eq!(full.to_input_string(), "BB?\nW..\n..B");
+
+        let mut incremental = grid.clone();
+        let result = SingleLiberties
+            .apply_incremental(&mut incremental, &[Coord::new(2, 2)])
+            .unwrap_or_else(|| SingleLiberties.apply(&mut incremental));
+        assert_eq!(result, Ok(true));
+        assert_eq!(incremental.to_input_string(), full.to_input_string());
+    }
 }
@@ -0,0 +1,74 @@
+use crate::grid::bounds::Bounds;
+use crate::{Grid, State};
+
+use super::{MarkSet, Strategy, StrategyResult};
+
+/// For variants with strip walls (e.g. Nuribou): marks unknown cells white if marking them black
+/// would bend a strip or join strips into a bent one.
+pub struct Strips;
+
+impl Strategy for Strips {
+    fn name(&self) -> &str {
+        "Strips"
+    }
+
+    fn apply(&self, grid: &mut Grid) -> StrategyResult {
+        if !grid.rules().has_strip_walls() {
+            return Ok(false);
+        }
+
+        let mut mark_set = MarkSet::new();
+
+        for (coord, cell) in grid.iter() {
+            if cell.state.is_some() {
+                continue;
+            }
+
+            let mut region_ids = grid
+                .valid_neighbors(coord)
+                .filter_map(|adj_coord| grid.region_id(adj_coord))
+                .filter(|&region_id| grid.region(region_id).unwrap().state.is_black())
+                .collect::<Vec<_>>();
+            region_ids.sort_unstable_by_key(|region_id| region_id.to_index());
+            region_ids.dedup();
+
+            // The black region that marking the cell black would create
+            let mut bounds = Bounds::new(coord);
+            let mut len = 1;
+            for region_id in region_ids {
+                let region = grid.region(region_id).unwrap();
+                bounds = grid
+                    .region_coords(region)
+                    .fold(bounds, |bounds, coord| bounds.extend(coord));
+                len += region.len();
+            }
+
+            if !Grid::is_strip(bounds, len) {
+                mark_set.insert(coord, State::White);
+            }
+        }
+
+        mark_set.apply(grid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::strategy::test_strategy;
+
+    use super::Strips;
+
+    test_strategy!(
+        test_side_of_strip,
+        Strips,
+        "# variant: nuribou\n....\n.BB.\n....",
+        Some("# variant: nuribou\n.WW.\n.BB.\n.WW.")
+    );
+    test_strategy!(
+        test_corner,
+        Strips,
+        "# variant: nuribou\nB.\n.B",
+        Some("# variant: nuribou\nBW\nWB")
+    );
+    test_strategy!(test_nurikabe, Strips, "B.\n.B", None);
+}
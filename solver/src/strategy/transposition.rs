@@ -0,0 +1,69 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+/// A cache of outcomes per [Zobrist hash](crate::Grid::zobrist_hash) of the grid they were
+/// computed for.
+///
+/// Hash collisions are not detected, so with many entries there is a tiny chance of returning the
+/// outcome for a different grid.
+pub(crate) struct TranspositionTable<T> {
+    capacity: usize,
+    entries: Mutex<HashMap<u64, T>>,
+    hits: AtomicUsize,
+}
+
+impl<T: Clone> TranspositionTable<T> {
+    /// Creates a table holding at most `capacity` entries. Once full, the table is cleared before
+    /// inserting more entries.
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: Mutex::new(HashMap::new()),
+            hits: AtomicUsize::new(0),
+        }
+    }
+
+    pub(crate) fn get(&self, hash: u64) -> Option<T> {
+        let entry = self.entries.lock().unwrap().get(&hash).cloned();
+        if entry.is_some() {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+        }
+        entry
+    }
+
+    pub(crate) fn insert(&self, hash: u64, value: T) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() >= self.capacity {
+            entries.clear();
+        }
+        entries.insert(hash, value);
+    }
+
+    /// Returns the number of lookups that found an entry.
+    pub(crate) fn hits(&self) -> usize {
+        self.hits.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TranspositionTable;
+
+    #[test]
+    fn test_capacity() {
+        let table = TranspositionTable::new(2);
+        table.insert(1, "a");
+        table.insert(2, "b");
+        assert_eq!(table.get(1), Some("a"));
+
+        table.insert(3, "c");
+        assert_eq!(table.get(1), None);
+        assert_eq!(table.get(3), Some("c"));
+        assert_eq!(table.hits(), 2);
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{Coord, Grid, State};
+use crate::{Grid, State};
 
 use super::{MarkSet, Strategy, StrategyResult};
 
@@ -12,13 +12,10 @@ impl Strategy for UnreachableCells {
     fn apply(&self, grid: &mut Grid) -> StrategyResult {
         let mut mark_set = MarkSet::new();
 
-        for col in 0..grid.num_cols {
-            for row in 0..grid.num_rows {
-                let coord = Coord::new(row, col);
-                if grid.is_cell_unreachable(coord, mark_set.mark_as_black.iter().copied()) {
-                    mark_set.insert(coord, State::Black);
-                }
-            }
+        // Marking an unreachable cell black can't make any other cell unreachable, so all of them
+        // can be found in a single sweep.
+        for &coord in grid.unreachable_cells() {
+            mark_set.insert(coord, State::Black);
         }
 
         mark_set.apply(grid)